@@ -2,241 +2,222 @@ extern crate rs25;
 extern crate vocs;
 extern crate i73;
 extern crate java_rand;
+
+#[cfg(test)]
 #[macro_use]
-extern crate serde_json;
-
-use std::path::PathBuf;
-use std::fs::File;
-use std::cmp::min;
-
-use i73::config::settings::customized::{Customized, Parts};
-use i73::generator::Pass;
-use i73::generator::overworld_173::{self, Settings};
-use i73::config::biomes::{BiomesConfig, DecoratorConfig};
-use i73::biome::Lookup;
-use i73::structure;
+extern crate serde_derive;
+#[cfg(test)]
+extern crate nbt_serde;
+
+use std::path::{Path, PathBuf};
+use std::fs::{self, File};
+use std::cmp::{min, max};
+use std::time::{SystemTime, Duration, UNIX_EPOCH};
+
+use i73::config::profile::Profile;
+
+use java_rand::Random;
+
+use i73::world::region::RegionStream;
+use i73::world::extras::ColumnExtras;
+use i73::lighting;
+use i73::output::level::{LevelData, VERSION_ANVIL, VERSION_MCREGION};
+use i73::output::mcregion;
+use i73::lighting::AreaLight;
+use i73::world::WorldGenerator;
 use i73::matcher::BlockMatcher;
 
 use vocs::indexed::ChunkIndexed;
 use vocs::world::world::World;
-use vocs::view::ColumnMut;
 use vocs::position::{GlobalColumnPosition, GlobalChunkPosition};
 
 use rs25::level::manager::{ColumnSnapshot, ChunkSnapshot};
 use rs25::level::region::RegionWriter;
 use rs25::level::anvil::ColumnRoot;
 
-fn main() {
-	let profile_name = match ::std::env::args().skip(1).next() {
-		Some(name) => name,
-		None => {
-			println!("Usage: i73 <profile>");
-			return;
+const USAGE: &'static str = "Usage: i73 <profile> [options]
+Options:
+  --seed <seed>                  Numeric seed, or text that is hashed like a Java string seed. Random if not specified.
+  --output <directory>           World folder to write the generated regions to. Defaults to \"out\".
+  --regions <x0> <z0> <x1> <z1>  Generates the rectangle of regions from (x0, z0) to (x1, z1), inclusive. Defaults to region (0, 0).
+  --radius <radius>              Generates every chunk within <radius> chunks of the center, instead of whole regions.
+  --center <x> <z>               Center chunk for --radius. Defaults to chunk (0, 0).
+  --format <anvil|mcregion>      Region file format. McRegion worlds can be opened by Beta 1.7.3. Defaults to anvil.
+
+<profile> is either a path to a profile directory, or the name of a profile in the profiles/ directory.";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Format {
+	Anvil,
+	McRegion
+}
+
+impl Format {
+	fn parse(format: &str) -> Result<Self, String> {
+		match format {
+			"anvil"    => Ok(Format::Anvil),
+			"mcregion" => Ok(Format::McRegion),
+			_ => Err(format!("unknown format: {}", format))
 		}
-	};
-	
-	let mut profile = PathBuf::new();
-	profile.push("profiles");
-	profile.push(&profile_name);
-	
-	println!("Using profile {}: {}", profile_name, profile.to_string_lossy());
-	
-	let customized = serde_json::from_reader::<File, Customized>(File::open(profile.join("customized.json")).unwrap()).unwrap();
-	let parts = Parts::from(customized);
-	
-	println!("  Tri Noise Settings: {:?}", parts.tri);
-	println!("  Height Stretch: {:?}", parts.height_stretch);
-	println!("  Height Settings: {:?}", parts.height);
-	println!("  Biome Settings: {:?}", parts.biome);
-	println!("  Structures: {:?}", parts.structures);
-	println!("  Decorators: {:?}", parts.decorators);
-	
-	let mut settings = Settings::default();
-	
-	settings.tri = parts.tri;
-	settings.height = parts.height.into();
-	settings.field.height_stretch = parts.height_stretch;
-	
-	// TODO: Biome Settings
-	
-	let sea_block = if parts.ocean.top > 0 {
-		settings.sea_coord = min(parts.ocean.top - 1, 255) as u8;
-		
-		if parts.ocean.lava { 11*16 } else { 9*16 }
-	} else {
-		0*16
-	};
-	
-	settings.shape_blocks.ocean = sea_block;
-	settings.paint_blocks.ocean = sea_block;
+	}
 
-	// TODO: Structures and Decorators
-	
-	let biomes_config = serde_json::from_reader::<File, BiomesConfig>(File::open(profile.join("biomes.json")).unwrap()).unwrap();
-	let grid = biomes_config.to_grid().unwrap();
-
-	let mut decorator_registry: ::std::collections::HashMap<String, Box<i73::decorator::DecoratorFactory<u16>>> = ::std::collections::HashMap::new();
-	decorator_registry.insert("vein".into(), Box::new(::i73::decorator::vein::VeinDecoratorFactory::default()));
-	decorator_registry.insert("seaside_vein".into(), Box::new(::i73::decorator::vein::SeasideVeinDecoratorFactory::default()));
-
-	let gravel_config = DecoratorConfig {
-		decorator: "vein".into(),
-		settings: json!({
-			"blocks": {
-				"replace": {
-					"blacklist": false,
-					"blocks": [16]
-				},
-				"block": 208
-			},
-			"size": 32
-		}),
-		height_distribution: ::i73::distribution::Chance {
-			base: i73::distribution::Baseline::Linear(i73::distribution::Linear {
-				min: 0,
-				max: 63
-			}),
-			ordering: i73::distribution::ChanceOrdering::AlwaysGeneratePayload,
-			chance: 1
-		},
-		count: ::i73::distribution::Chance {
-			base: i73::distribution::Baseline::Linear(i73::distribution::Linear {
-				min: 0,
-				max: 9
-			}),
-			ordering: i73::distribution::ChanceOrdering::AlwaysGeneratePayload,
-			chance: 1
+	fn extension(&self) -> &'static str {
+		match *self {
+			Format::Anvil    => "mca",
+			Format::McRegion => "mcr"
 		}
-	};
+	}
 
-	let mut decorators: Vec<::i73::decorator::Dispatcher<i73::distribution::Chance<i73::distribution::Baseline>, i73::distribution::Chance<i73::distribution::Baseline>, u16>> = Vec::new();
-
-	decorators.push (::i73::decorator::Dispatcher {
-		decorator: Box::new(::i73::decorator::lake::LakeDecorator {
-			blocks: ::i73::decorator::lake::LakeBlocks {
-				is_liquid:  BlockMatcher::include([8*16, 9*16, 10*16, 11*16].iter()),
-				is_solid:   BlockMatcher::exclude([0*16, 8*16, 9*16, 10*16, 11*16].iter()), // TODO: All nonsolid blocks
-				replacable: BlockMatcher::none(), // TODO
-				liquid:     9*16,
-				carve:      0*16,
-				solidify:   None
-			},
-			settings: ::i73::decorator::lake::LakeSettings::default()
-		}),
-		height_distribution: ::i73::distribution::Chance {
-			base: i73::distribution::Baseline::Linear(i73::distribution::Linear {
-				min: 0,
-				max: 127
-			}),
-			ordering: i73::distribution::ChanceOrdering::AlwaysGeneratePayload,
-			chance: 1
-		},
-		rarity: ::i73::distribution::Chance {
-			base: ::i73::distribution::Baseline::Constant { value: 1 },
-			chance: 4,
-			ordering: ::i73::distribution::ChanceOrdering::AlwaysGeneratePayload
+	fn version(&self) -> i32 {
+		match *self {
+			Format::Anvil    => VERSION_ANVIL,
+			Format::McRegion => VERSION_MCREGION
 		}
-	});
-
-	decorators.push (::i73::decorator::Dispatcher {
-		decorator: Box::new(::i73::decorator::vein::SeasideVeinDecorator {
-			vein: ::i73::decorator::vein::VeinDecorator {
-				blocks: ::i73::decorator::vein::VeinBlocks {
-					replace: BlockMatcher::is(12*16),
-					block: 82*16
-				},
-				size: 32
-			},
-			ocean: BlockMatcher::include([8*16, 9*16].iter())
-		}),
-		height_distribution: ::i73::distribution::Chance {
-			base: i73::distribution::Baseline::Linear(i73::distribution::Linear {
-				min: 0,
-				max: 63
-			}),
-			ordering: i73::distribution::ChanceOrdering::AlwaysGeneratePayload,
-			chance: 1
-		},
-		rarity: ::i73::distribution::Chance {
-			base: i73::distribution::Baseline::Linear(i73::distribution::Linear {
-				min: 0,
-				max: 9
-			}),
-			ordering: i73::distribution::ChanceOrdering::AlwaysGeneratePayload,
-			chance: 1
-		}
-	});
-
-	decorators.push (gravel_config.into_dispatcher(&decorator_registry).unwrap());
-
-	decorators.push (::i73::decorator::Dispatcher {
-		decorator: Box::new(::i73::decorator::clump::Clump {
-			iterations: 64,
-			horizontal: 8,
-			vertical: 4,
-			decorator: ::i73::decorator::clump::plant::PlantDecorator {
-				block: 31*16 + 1,
-				base: BlockMatcher::include([2*16, 3*16, 60*16].into_iter()),
-				replace: BlockMatcher::is(0*16)
-			},
-			phantom: ::std::marker::PhantomData::<u16>
-		}),
-		height_distribution: ::i73::distribution::Chance {
-			base: i73::distribution::Baseline::Linear(i73::distribution::Linear {
-				min: 0,
-				max: 127
-			}),
-			ordering: i73::distribution::ChanceOrdering::AlwaysGeneratePayload,
-			chance: 1
-		},
-		rarity: ::i73::distribution::Chance {
-			base: i73::distribution::Baseline::Linear(i73::distribution::Linear {
-				min: 0,
-				max: 90
-			}),
-			ordering: i73::distribution::ChanceOrdering::AlwaysGeneratePayload,
-			chance: 1
+	}
+}
+
+struct Options {
+	profile: PathBuf,
+	seed: u64,
+	output: PathBuf,
+	format: Format,
+	/// Minimum corner of the generated area, inclusive.
+	from: GlobalColumnPosition,
+	/// Maximum corner of the generated area, exclusive.
+	to: GlobalColumnPosition
+}
+
+impl Options {
+	fn parse<I>(mut args: I) -> Result<Self, String> where I: Iterator<Item=String> {
+		let profile = args.next().ok_or_else(|| "missing profile".to_string())?;
+
+		let mut seed = None;
+		let mut output = PathBuf::from("out");
+		let mut format = Format::Anvil;
+		let mut regions = (0, 0, 0, 0);
+		let mut radius = None;
+		let mut center = (0, 0);
+
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--seed"    => seed = Some(parse_seed(&next_arg(&mut args, &arg)?)),
+				"--output"  => output = PathBuf::from(next_arg(&mut args, &arg)?),
+				"--format"  => format = Format::parse(&next_arg(&mut args, &arg)?)?,
+				"--regions" => regions = (
+					next_i32(&mut args, &arg)?,
+					next_i32(&mut args, &arg)?,
+					next_i32(&mut args, &arg)?,
+					next_i32(&mut args, &arg)?
+				),
+				"--radius"  => radius = Some(next_i32(&mut args, &arg)?),
+				"--center"  => center = (next_i32(&mut args, &arg)?, next_i32(&mut args, &arg)?),
+				_ => return Err(format!("unknown option: {}", arg))
+			}
 		}
-	});
 
-	/*use decorator::large_tree::{LargeTreeSettings, LargeTree};
-	let settings = LargeTreeSettings::default();
-	
-	for i in 0..1 {
-		let mut rng = Random::new(100 + i);
-		let shape = settings.tree((0, 0, 0), &mut rng, None, 20);
-		
-		println!("{:?}", shape);
-		
-		let mut y = shape.foliage_max_y - 1;
-		while y >= shape.foliage_min_y {
-			let spread = shape.spread(y);
-			
-			println!("y: {}, spread: {}", y, spread);
-			
-			for _ in 0..shape.foliage_per_y {
-				println!("{:?}", shape.foliage(y, spread, &mut rng));
-			}
-			
-			y -= 1;
+		let (from, to) = match radius {
+			Some(radius) if radius >= 0 => (
+				GlobalColumnPosition::new(center.0 - radius,     center.1 - radius    ),
+				GlobalColumnPosition::new(center.0 + radius + 1, center.1 + radius + 1)
+			),
+			Some(radius) => return Err(format!("radius must not be negative, got {}", radius)),
+			None => (
+				GlobalColumnPosition::new( min(regions.0, regions.2) * 32,         min(regions.1, regions.3) * 32       ),
+				GlobalColumnPosition::new((max(regions.0, regions.2) + 1) * 32, (max(regions.1, regions.3) + 1) * 32)
+			)
+		};
+
+		Ok(Options {
+			profile: resolve_profile(profile),
+			seed: seed.unwrap_or_else(random_seed),
+			output,
+			format,
+			from,
+			to
+		})
+	}
+
+	fn columns(&self) -> u64 {
+		((self.to.x() - self.from.x()) as u64) * ((self.to.z() - self.from.z()) as u64)
+	}
+}
+
+fn next_arg<I>(args: &mut I, option: &str) -> Result<String, String> where I: Iterator<Item=String> {
+	args.next().ok_or_else(|| format!("missing value for {}", option))
+}
+
+fn next_i32<I>(args: &mut I, option: &str) -> Result<i32, String> where I: Iterator<Item=String> {
+	let value = next_arg(args, option)?;
+
+	value.parse::<i32>().map_err(|e| format!("invalid value for {}: {} ({})", option, value, e))
+}
+
+/// Uses the profile name as a path if it points to an existing directory, otherwise looks it up in `profiles/`.
+fn resolve_profile(profile: String) -> PathBuf {
+	let path = PathBuf::from(&profile);
+
+	if path.is_dir() {
+		return path;
+	}
+
+	let mut named = PathBuf::new();
+	named.push("profiles");
+	named.push(&profile);
+
+	named
+}
+
+/// Interprets a seed the same way as the Beta world creation screen: numbers are used as-is,
+/// and any other text is hashed with Java's `String.hashCode()`. Empty text or a seed of 0 results in a random seed.
+fn parse_seed(seed: &str) -> u64 {
+	if seed.is_empty() {
+		return random_seed();
+	}
+
+	match seed.parse::<i64>() {
+		Ok(0) => random_seed(),
+		Ok(seed) => seed as u64,
+		Err(_) => java_string_hash(seed) as i64 as u64
+	}
+}
+
+/// Equivalent to Java's `String.hashCode()`, which operates on UTF-16 code units.
+fn java_string_hash(string: &str) -> i32 {
+	string.encode_utf16().fold(0i32, |hash, unit| hash.wrapping_mul(31).wrapping_add(unit as i32))
+}
+
+fn random_seed() -> u64 {
+	let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+	let nanos = now.as_secs().wrapping_mul(1000000000).wrapping_add(now.subsec_nanos() as u64);
+
+	Random::new(nanos).next_i64() as u64
+}
+
+fn main() {
+	let options = match Options::parse(::std::env::args().skip(1)) {
+		Ok(options) => options,
+		Err(message) => {
+			println!("Error: {}", message);
+			println!("{}", USAGE);
+			return;
 		}
-	}*/
+	};
 	
-	let (shape, paint) = overworld_173::passes(8399452073110208023, settings, Lookup::generate(&grid));
+	println!("Using profile {}", options.profile.to_string_lossy());
+	println!("Using seed {}", options.seed as i64);
+	println!("Generating chunks from ({}, {}) to ({}, {}) into {}", options.from.x(), options.from.z(), options.to.x() - 1, options.to.z() - 1, options.output.to_string_lossy());
 	
-	let caves_generator = structure::caves::CavesGenerator {
-		carve: 0*16,
-		lower: 10*16,
-		surface_block: 2*16,
-		ocean: BlockMatcher::include([8*16, 9*16].iter()),
-		carvable: BlockMatcher::include([1*16, 2*16, 3*16].iter()),
-		surface_top: BlockMatcher::is(2*16),
-		surface_fill: BlockMatcher::is(3*16),
-		blob_size_multiplier: 1.0,
-		vertical_multiplier: 1.0,
-		lower_surface: 10
-	};
-	let caves = structure::StructureGenerateNearby::new(8399452073110208023, 8, caves_generator);
+	let profile = Profile::load(&options.profile).unwrap();
+	
+	println!("  Tri Noise Settings: {:?}", profile.parts.tri);
+	println!("  Height Stretch: {:?}", profile.parts.height_stretch);
+	println!("  Height Settings: {:?}", profile.parts.height);
+	println!("  Biome Settings: {:?}", profile.parts.biome);
+	println!("  Structures: {:?}", profile.parts.structures);
+	println!("  Decorators: {:?}", profile.parts.decorators);
+	
+	let blocks = profile.blocks.clone();
+	let generator = profile.into_overworld(options.seed).unwrap();
 	
 	/*let shape = nether_173::passes(-160654125608861039, &nether_173::default_tri_settings(), nether_173::ShapeBlocks::default(), 31);
 	
@@ -250,370 +231,263 @@ fn main() {
 	
 	let (_, paint) = overworld_173::passes(-160654125608861039, fake_settings);*/
 	
-	let mut world = World::<ChunkIndexed<u16>>::new();
+	let mut stream = RegionStream::new(options.from, options.to);
+	let columns = options.columns();
 
-	println!("Generating region (0, 0)");
-	let gen_start = ::std::time::Instant::now();
+	let region_directory = options.output.join("region");
+	fs::create_dir_all(&region_directory).unwrap();
 
-	for x in 0..32 {
-		println!("{}", x);
-		for z in 0..32 {
-			let column_position = GlobalColumnPosition::new(x, z);
+	// use rs25::level::manager::{Manager, RegionPool};
+	// let pool = RegionPool::new(PathBuf::from("out/region/"), 512);
+	// let mut manager = Manager::manage(pool);
 
-			let mut column_chunks = [
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0),
-				ChunkIndexed::<u16>::new(4, 0)
-			];
-
-			{
-				let mut column: ColumnMut<u16> = ColumnMut::from_array(&mut column_chunks);
-
-				shape.apply(&mut column, column_position);
-				paint.apply(&mut column, column_position);
-				caves.apply(&mut column, column_position);
-			}
+	println!("Generating {} columns", columns);
+	let gen_start = ::std::time::Instant::now();
 
-			world.set_column(column_position, column_chunks);
-		}
-	}
+	for region in stream.regions() {
+		println!("Generating region ({}, {})", region.x(), region.z());
 
-	{
-		let end = ::std::time::Instant::now();
-		let time = end.duration_since(gen_start);
+		stream.prepare(&generator, region).unwrap();
 
-		let secs = time.as_secs();
-		let us = (secs * 1000000) + ((time.subsec_nanos() / 1000) as u64);
+		let (lower, upper) = stream.bounds(region);
+		let mut light = lighting::light_area(stream.world(), &blocks, lower, upper);
 
-		println!("Generation done in {}us ({}us per column)", us, us / 1024);
-	}
+		println!("Writing region ({}, {})", region.x(), region.z());
 
-	println!("Decorating region (0, 0)");
-	let dec_start = ::std::time::Instant::now();
+		let path = region_directory.join(format!("r.{}.{}.{}", region.x(), region.z(), options.format.extension()));
 
-	let mut decoration_rng = ::java_rand::Random::new(8399452073110208023);
-	let coefficients = (
-		((decoration_rng.next_i64() >> 1) << 1) + 1,
-		((decoration_rng.next_i64() >> 1) << 1) + 1
-	);
+		match options.format {
+			Format::Anvil    => write_anvil(&path, &mut stream, &generator, &mut light, lower, upper),
+			Format::McRegion => write_mcregion(&path, &mut stream, &mut light, lower, upper)
+		}
 
-	for x in 0..31 {
-		println!("{}", x);
-		for z in 0..31 {
-			let x_part = (x as i64).wrapping_mul(coefficients.0) as u64;
-			let z_part = (z as i64).wrapping_mul(coefficients.1) as u64;
-			decoration_rng = ::java_rand::Random::new((x_part.wrapping_add(z_part)) ^ 8399452073110208023);
+		stream.release(region);
+	}
 
-			let mut quad = world.get_quad_mut(GlobalColumnPosition::new(x as i32, z as i32)).unwrap();
+	if stream.dropped_spills() > 0 {
+		println!("Warning: Dropped {} blocks that decorators placed into columns that were already written or outside of the area", stream.dropped_spills());
+	}
 
-			for dispatcher in &decorators {
-				dispatcher.generate(&mut quad, &mut decoration_rng).unwrap();
-			}
-		}
+	if stream.skipped_decorations() > 0 {
+		println!("Warning: Skipped {} decoration attempts that were outside of the area being decorated", stream.skipped_decorations());
 	}
 
 	{
 		let end = ::std::time::Instant::now();
-		let time = end.duration_since(dec_start);
+		let time = end.duration_since(gen_start);
 
 		let secs = time.as_secs();
 		let us = (secs * 1000000) + ((time.subsec_nanos() / 1000) as u64);
 
-		println!("Decoration done in {}us ({}us per column)", us, us / 1024);
+		println!("Generation done in {}us ({}us per column)", us, us / columns);
 	}
 
-	use vocs::nibbles::{u4, ChunkNibbles, BulkNibbles};
-	use vocs::mask::ChunkMask;
-	use vocs::sparse::SparseStorage;
-	use vocs::mask::LayerMask;
-	use vocs::component::*;
-	use vocs::view::{SplitDirectional, Directional};
-	use rs25::dynamics::light::{SkyLightSources, Lighting, HeightMapBuilder};
-	use rs25::dynamics::queue::Queue;
-	use vocs::position::{Offset, dir};
+	let name = options.output.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| "i73".to_string());
+	let mut level = LevelData::new(name, options.seed, options.format.version());
 
-	use vocs::world::shared::{NoPack, SharedWorld};
+	println!("Finding spawn point");
 
-	let mut sky_light = SharedWorld::<NoPack<ChunkNibbles>>::new();
-	let mut incomplete = World::<ChunkMask>::new();
-	let mut heightmaps = ::std::collections::HashMap::<(i32, i32), Vec<u32>>::new(); // TODO: Better vocs integration.
+	match generator.find_spawn(&BlockMatcher::is(12*16), &mut Random::new(options.seed)) {
+		Some((x, y, z)) => {
+			println!("Spawn point: ({}, {}, {})", x, y, z);
 
-	let mut lighting_info = SparseStorage::<u4>::with_default(u4::new(15));
-	lighting_info.set( 0 * 16, u4::new(0));
-	lighting_info.set( 8 * 16, u4::new(2));
-	lighting_info.set( 9 * 16, u4::new(2));
+			level.spawn_x = x;
+			level.spawn_y = y;
+			level.spawn_z = z;
+		},
+		None => println!("Warning: No sand found to spawn on, spawning at the origin")
+	}
 
-	let empty_lighting = ChunkNibbles::default();
+	level.last_played = {
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
 
-	let mut queue = Queue::default();
+		(now.as_secs() * 1000 + (now.subsec_nanos() / 1000000) as u64) as i64
+	};
 
-	println!("Performing initial sky lighting for region (0, 0)");
-	let lighting_start = ::std::time::Instant::now();
+	println!("Writing level.dat");
 
-	fn spill_out(chunk_position: GlobalChunkPosition, incomplete: &mut World<ChunkMask>, old_spills: vocs::view::Directional<LayerMask>) {
-		if let Some(up) = chunk_position.plus_y() {
-			if !old_spills[dir::Up].is_filled(false) {
-				incomplete.get_or_create_mut(up).layer_zx_mut(0).combine(&old_spills[dir::Up]);
-			}
-		}
+	let mut file = File::create(options.output.join("level.dat")).unwrap();
+	level.write(&mut file).unwrap();
+}
 
-		if let Some(down) = chunk_position.minus_y() {
-			if !old_spills[dir::Down].is_filled(false) {
-				incomplete.get_or_create_mut(down).layer_zx_mut(15).combine(&old_spills[dir::Down]);
-			}
-		}
+/// Writes the columns from `lower` (inclusive) to `upper` (exclusive) to an Anvil region file.
+fn write_anvil(path: &Path, stream: &mut RegionStream<u16>, generator: &WorldGenerator<u16>, light: &mut AreaLight, lower: GlobalColumnPosition, upper: GlobalColumnPosition) {
+	let file = File::create(path).unwrap();
+	let mut writer = RegionWriter::start(file).unwrap();
 
-		if let Some(plus_x) = chunk_position.plus_x() {
-			if !old_spills[dir::PlusX].is_filled(false) {
-				incomplete.get_or_create_mut(plus_x).layer_zy_mut(0).combine(&old_spills[dir::PlusX]);
-			}
-		}
+	for z in lower.z()..upper.z() {
+		for x in lower.x()..upper.x() {
+			let column_position = GlobalColumnPosition::new(x, z);
 
-		if let Some(minus_x) = chunk_position.minus_x() {
-			if !old_spills[dir::MinusX].is_filled(false) {
-				incomplete.get_or_create_mut(minus_x).layer_zy_mut(15).combine(&old_spills[dir::MinusX]);
-			}
-		}
+			let extras = stream.take_extras(column_position);
+			let root = anvil_column(stream.world(), light, generator.biome_ids(column_position), extras, column_position);
 
-		if let Some(plus_z) = chunk_position.plus_z() {
-			if !old_spills[dir::PlusZ].is_filled(false) {
-				incomplete.get_or_create_mut(plus_z).layer_yx_mut(0).combine(&old_spills[dir::PlusZ]);
-			}
-		}
-
-		if let Some(minus_z) = chunk_position.minus_z() {
-			if !old_spills[dir::MinusZ].is_filled(false) {
-				incomplete.get_or_create_mut(minus_z).layer_yx_mut(15).combine(&old_spills[dir::MinusZ]);
-			}
+			writer.chunk((x & 31) as u8, (z & 31) as u8, &root).unwrap();
 		}
 	}
 
-	for x in 0..32 {
-		println!("{}", x);
-		for z in 0..32 {
-			let column_position = GlobalColumnPosition::new(x, z);
-
-			let mut mask = LayerMask::default();
-			let mut heightmap = HeightMapBuilder::new();
-			let mut heightmap_sections = [None, None, None, None, None, None, None, None, None, None, None, None, None, None, None, None];
-
-			for y in (0..16).rev() {
-				let chunk_position = GlobalChunkPosition::from_column(column_position, y);
-
-				let (blocks, palette) = world.get(chunk_position).unwrap().freeze();
+	writer.finish().unwrap();
+}
+
+/// Builds the Anvil NBT of a column, including the tile entities, entities, and tile ticks created by the populator.
+fn anvil_column(world: &World<ChunkIndexed<u16>>, light: &mut AreaLight, biomes: Vec<u8>, extras: ColumnExtras<u16>, column_position: GlobalColumnPosition) -> ColumnRoot {
+	let heightmap = light.take_heightmap(column_position).unwrap();
+
+	let mut snapshot = ColumnSnapshot {
+		chunks: vec![None; 16],
+		last_update: 0,
+		light_populated: true,
+		terrain_populated: true,
+		inhabited_time: 0,
+		biomes,
+		heightmap,
+		entities: extras.entities,
+		tile_entities: extras.tile_entities,
+		tile_ticks: extras.tile_ticks.iter().map(|tick| tick.to_nbt()).collect()
+	};
 
-				let mut opacity = BulkNibbles::new(palette.len());
+	for y in 0..16 {
+		let chunk_position = GlobalChunkPosition::from_column(column_position, y);
 
-				for (index, value) in palette.iter().enumerate() {
-					opacity.set(index, value.map(|entry| lighting_info.get(entry as usize)).unwrap_or(lighting_info.default_value()));
-				}
+		let chunk = world.get(chunk_position).unwrap();
+		let sky_light = light.take_sky_light(chunk_position);
+		let block_light = light.take_block_light(chunk_position);
 
-				let sources = SkyLightSources::build(blocks, &opacity, mask);
+		if chunk.anvil_empty() {
+			continue;
+		}
 
-				let mut light_data = ChunkNibbles::default();
-				let neighbors = Directional::combine(SplitDirectional {
-					minus_x: &empty_lighting,
-					plus_x: &empty_lighting,
-					minus_z: &empty_lighting,
-					plus_z: &empty_lighting,
-					down: &empty_lighting,
-					up: &empty_lighting
-				});
+		snapshot.chunks[y as usize] = Some(ChunkSnapshot {
+			blocks: chunk.clone(),
+			block_light,
+			sky_light
+		});
+	};
 
-				let sources = {
-					let mut light = Lighting::new(&mut light_data, neighbors, sources, opacity);
+	ColumnRoot::from(snapshot.to_column(column_position.x(), column_position.z()).unwrap())
+}
 
-					light.initial(blocks, &mut queue);
-					light.finish(blocks, &mut queue);
+/// Writes the columns from `lower` (inclusive) to `upper` (exclusive) to a McRegion region file, dropping everything
+/// above the 128 block height limit.
+fn write_mcregion(path: &Path, stream: &mut RegionStream<u16>, light: &mut AreaLight, lower: GlobalColumnPosition, upper: GlobalColumnPosition) {
+	let file = File::create(path).unwrap();
+	let mut writer = mcregion::RegionWriter::start(file);
 
-					light.decompose().1
-				};
+	for z in lower.z()..upper.z() {
+		for x in lower.x()..upper.x() {
+			let column_position = GlobalColumnPosition::new(x, z);
+			let mut column = mcregion::Column::new(x, z);
 
-				heightmap_sections[y as usize] = Some(sources.clone());
-				mask = heightmap.add(sources);
+			column.set_heightmap(&light.take_heightmap(column_position).unwrap());
+			column.set_extras(stream.take_extras(column_position));
 
-				let old_spills = queue.reset_spills();
+			for y in 0..mcregion::CHUNKS {
+				let chunk_position = GlobalChunkPosition::from_column(column_position, y);
 
-				spill_out(chunk_position, &mut incomplete, old_spills);
+				let chunk = stream.world().get(chunk_position).unwrap();
+				let sky_light = light.take_sky_light(chunk_position);
+				let block_light = light.take_block_light(chunk_position);
 
-				sky_light.set(chunk_position, NoPack(light_data));
+				column.set_chunk(y, chunk, &sky_light, &block_light);
 			}
 
-			let heightmap = heightmap.build();
-
-			/*for (index, part) in heightmap_sections.iter().enumerate() {
-				let part = part.as_ref().unwrap().clone();
-
-				assert_eq!(SkyLightSources::slice(&heightmap, index as u8), part);
-			}*/
-
-			heightmaps.insert((x, z), heightmap.into_vec());
+			writer.chunk((x & 31) as u8, (z & 31) as u8, column).unwrap();
 		}
 	}
 
-	{
-		let end = ::std::time::Instant::now();
-		let time = end.duration_since(lighting_start);
-
-		let secs = time.as_secs();
-		let us = (secs * 1000000) + ((time.subsec_nanos() / 1000) as u64);
-
-		println!("Initial sky lighting done in {}us ({}us per column)", us, us / 1024);
+	writer.finish().unwrap();
+}
+
+#[cfg(test)]
+mod test {
+	use super::{java_string_hash, parse_seed, anvil_column};
+	use i73::lighting::AreaLight;
+	use i73::world::extras::ColumnExtras;
+	use i73::entity::tile::{TileEntity, PlacedTileEntity, ItemStack};
+	use vocs::indexed::ChunkIndexed;
+	use vocs::world::world::World;
+	use vocs::world::shared::SharedWorld;
+	use vocs::position::GlobalColumnPosition;
+	use std::collections::HashMap;
+	use nbt_serde;
+
+	#[derive(Deserialize)]
+	struct ColumnNbt {
+		#[serde(rename="Level")] level: LevelNbt
 	}
 
-	println!("Completing sky lighting for region (0, 0)");
-	let complete_lighting_start = ::std::time::Instant::now();
-
-	while incomplete.sectors().len() > 0 {
-		let incomplete_front = ::std::mem::replace(&mut incomplete, World::new());
-
-		for (sector_position, mut sector) in incomplete_front.into_sectors() {
-			println!("Completing sector @ {} - {} queued", sector_position, sector.count_sectors());
-
-			let block_sector = match world.get_sector(sector_position) {
-				Some(sector) => sector,
-				None => continue // No sense in lighting the void.
-			};
-
-			println!("(not skipped)");
-
-			let light_sector = sky_light.get_or_create_sector_mut(sector_position);
-
-			while let Some((position, incomplete)) = sector.pop_first() {
-				use vocs::mask::Mask;
-				println!("Completing chunk: {} / {} queued blocks", position, incomplete.count_ones());
-
-
-				let (blocks, palette) = block_sector[position].as_ref().unwrap().freeze();
-
-				let mut opacity = BulkNibbles::new(palette.len());
-
-				for (index, value) in palette.iter().enumerate() {
-					opacity.set(index, value.map(|entry| lighting_info.get(entry as usize)).unwrap_or(lighting_info.default_value()));
-				}
-
-				let column_pos = GlobalColumnPosition::combine(sector_position, position.layer());
-				let heightmap = heightmaps.get(&(column_pos.x(), column_pos.z())).unwrap();
-
-				let sources = SkyLightSources::slice(&heightmap, position.y());
-
-				// TODO: cross-sector lighting
-
-				let mut central = light_sector.get_or_create(position);
-				let locks = SplitDirectional {
-					up: position.offset(dir::Up).map(|position| light_sector[position].read()),
-					down: position.offset(dir::Down).map(|position| light_sector[position].read()),
-					plus_x: position.offset(dir::PlusX).map(|position| light_sector[position].read()),
-					minus_x: position.offset(dir::MinusX).map(|position| light_sector[position].read()),
-					plus_z: position.offset(dir::PlusZ).map(|position| light_sector[position].read()),
-					minus_z: position.offset(dir::MinusZ).map(|position| light_sector[position].read()),
-				};
-
-				let neighbors = SplitDirectional {
-					up: locks.up.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting),
-					down: locks.down.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting),
-					plus_x: locks.plus_x.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting),
-					minus_x: locks.minus_x.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting),
-					plus_z: locks.plus_z.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting),
-					minus_z: locks.minus_z.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting)
-				};
-
-				let mut light = Lighting::new(&mut central, Directional::combine(neighbors), sources, opacity);
-
-				queue.reset_from_mask(incomplete);
-				light.finish(blocks, &mut queue);
-
-				// TODO: Queue handling
-			}
-		}
+	#[derive(Deserialize)]
+	struct LevelNbt {
+		#[serde(rename="TileEntities")] tile_entities: Vec<TileEntityNbt>
 	}
 
-	{
-		let end = ::std::time::Instant::now();
-		let time = end.duration_since(complete_lighting_start);
-
-		let secs = time.as_secs();
-		let us = (secs * 1000000) + ((time.subsec_nanos() / 1000) as u64);
+	#[derive(Deserialize)]
+	struct TileEntityNbt {
+		id: String,
+		x: i32,
+		y: i32,
+		z: i32,
+		#[serde(rename="Items")] items: Vec<SlotNbt>
+	}
 
-		println!("Sky lighting completion done in {}us ({}us per column)", us, us / 1024);
+	#[derive(Deserialize)]
+	struct SlotNbt {
+		#[serde(rename="Slot")]  slot:  i8,
+		#[serde(rename="id")]    id:    i16,
+		#[serde(rename="Count")] count: i8
 	}
 
-	println!("Writing region (0, 0)");
-	let writing_start = ::std::time::Instant::now();
+	#[test]
+	fn test_seeds() {
+		assert_eq!(java_string_hash(""), 0);
+		assert_eq!(java_string_hash("hello"), 99162322);
+		assert_eq!(java_string_hash("Glacier"), 1772835215);
 
-	// use rs25::level::manager::{Manager, RegionPool};
-	// let pool = RegionPool::new(PathBuf::from("out/region/"), 512);
-	// let mut manager = Manager::manage(pool);
+		assert_eq!(parse_seed("8399452073110208023"), 8399452073110208023);
+		assert_eq!(parse_seed("-1"), (-1i64) as u64);
+		assert_eq!(parse_seed("Glacier"), 1772835215);
+	}
 
-	let file = File::create("out/region/r.0.0.mca").unwrap();
-	let mut writer = RegionWriter::start(file).unwrap();
+	#[test]
+	fn test_anvil_tile_entities() {
+		let position = GlobalColumnPosition::new(1, -2);
 
-	for z in 0..32 {
-		println!("{}", z);
-		for x in 0..32 {
-			let column_position = GlobalColumnPosition::new(x, z);
+		let mut world = World::<ChunkIndexed<u16>>::new();
+		world.set_column(position, [
+			ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0),
+			ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0),
+			ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0),
+			ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0)
+		]);
 
-			let heightmap = heightmaps.remove(&(x, z)).unwrap();
-
-			let mut snapshot = ColumnSnapshot {
-				chunks: vec![None; 16],
-				last_update: 0,
-				light_populated: true,
-				terrain_populated: true,
-				inhabited_time: 0,
-				biomes: vec![0; 256],
-				heightmap,
-				entities: vec![],
-				tile_entities: vec![],
-				tile_ticks: vec![]
-			};
-
-			for y in 0..16 {
-				let chunk_position = GlobalChunkPosition::from_column(column_position, y);
+		let mut heightmaps = HashMap::new();
+		heightmaps.insert((position.x(), position.z()), vec![0; 256]);
 
-				let chunk = world.get(chunk_position).unwrap();
+		let mut light = AreaLight { sky_light: SharedWorld::new(), block_light: SharedWorld::new(), heightmaps };
 
-				if chunk.anvil_empty() {
-					continue;
-				}
+		let mut extras = ColumnExtras::default();
+		extras.tile_entities.push(PlacedTileEntity {
+			x: 20,
+			y: 40,
+			z: -25,
+			tile_entity: TileEntity::Chest { items: vec![(3, ItemStack { id: 265, damage: 0, count: 4 })] }
+		});
 
-				let sky_light = sky_light.remove(chunk_position).unwrap()/*_or_else(ChunkNibbles::default)*/;
+		let root = anvil_column(&world, &mut light, vec![1; 256], extras, position);
 
-				snapshot.chunks[y as usize] = Some(ChunkSnapshot {
-					blocks: chunk.clone(),
-					block_light: ChunkNibbles::default(),
-					sky_light: sky_light.0
-				});
-			};
+		let mut nbt = Vec::new();
+		nbt_serde::encode::to_writer(&mut nbt, &root, None).unwrap();
 
-			let root = ColumnRoot::from(snapshot.to_column(x as i32, z as i32).unwrap());
+		let column: ColumnNbt = nbt_serde::decode::from_reader(&nbt[..]).unwrap();
+		let tile_entities = column.level.tile_entities;
 
-			writer.chunk(x as u8, z as u8, &root).unwrap();
-		}
-	}
-	
-	writer.finish().unwrap();
+		assert_eq!(tile_entities.len(), 1);
+		assert_eq!(tile_entities[0].id, "Chest");
+		assert_eq!((tile_entities[0].x, tile_entities[0].y, tile_entities[0].z), (20, 40, -25));
 
-	{
-		let end = ::std::time::Instant::now();
-		let time = end.duration_since(writing_start);
-
-		let secs = time.as_secs();
-		let us = (secs * 1000000) + ((time.subsec_nanos() / 1000) as u64);
+		let items = &tile_entities[0].items;
 
-		println!("Writing done in {}us ({}us per column)", us, us / 1024);
+		assert_eq!(items.len(), 1);
+		assert_eq!((items[0].slot, items[0].id, items[0].count), (3, 265, 4));
 	}
 }
\ No newline at end of file
@@ -9,7 +9,9 @@ use segmented::Segmented;
 #[derive(Eq, PartialEq, Hash, Clone, Debug)]
 pub struct Biome<B> where B: Target {
 	pub surface: Surface<B>,
-	pub name: Cow<'static, str>
+	pub name: Cow<'static, str>,
+	/// Numeric ID that is written to the biome array of each column.
+	pub id: u8
 }
 
 #[derive(Eq, PartialEq, Hash, Clone, Debug)]
@@ -57,6 +59,7 @@ impl<B> Grid<B> where B: Target {
 	}
 }
 
+#[derive(Clone)]
 pub struct Lookup<B>(Box<[Biome<B>]>) where B: Target;
 impl<B> Lookup<B> where B: Target {
 	pub fn filled(biome: &Biome<B>) -> Self {
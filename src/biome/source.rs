@@ -5,6 +5,7 @@ use vocs::indexed::{LayerIndexed, Target};
 use cgmath::{Point2, Vector2};
 use sample::Sample;
 
+#[derive(Clone)]
 pub struct BiomeSource<B> where B: Target {
 	climate: ClimateSource,
 	lookup:  Lookup<B>
@@ -15,6 +16,20 @@ impl<B> BiomeSource<B> where B: Target {
 		BiomeSource { climate, lookup }
 	}
 	
+	pub fn climate(&self) -> &ClimateSource {
+		&self.climate
+	}
+	
+	/// Samples the climate at a single block position.
+	pub fn climate_at(&self, x: i32, z: i32) -> Climate {
+		self.climate.sample(Point2::new(x as f64, z as f64))
+	}
+	
+	/// Looks up the biome at a single block position.
+	pub fn biome_at(&self, x: i32, z: i32) -> &Biome<B> {
+		self.lookup.lookup(self.climate_at(x, z))
+	}
+	
 	pub fn layer(&self, chunk: GlobalColumnPosition) -> LayerIndexed<Biome<B>> {
 		let block = Point2::new (
 			(chunk.x() * 16) as f64,
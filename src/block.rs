@@ -0,0 +1,171 @@
+//! Properties of block types, shared by every part of the generator that needs to know how a block behaves.
+
+use vocs::indexed::Target;
+use vocs::nibbles::u4;
+use vocs::sparse::SparseStorage;
+use matcher::BlockMatcher;
+use std::collections::HashMap;
+use std::cmp::min;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Liquid {
+	#[serde(rename="water")]
+	Water,
+	#[serde(rename="lava")]
+	Lava
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlockProperties {
+	/// How much light is lost when passing through this block, from 0 to 15.
+	#[serde(default="opaque")]
+	pub opacity: u8,
+	/// How much light this block emits, from 0 to 15.
+	#[serde(default)]
+	pub emission: u8,
+	/// Whether the block is a physical obstacle. Lakes will not generate next to non-solid blocks.
+	#[serde(default="yes")]
+	pub solid: bool,
+	/// Which kind of liquid this block is, if any.
+	#[serde(default)]
+	pub liquid: Option<Liquid>,
+	/// Whether generated structures may overwrite the block, such as air or tall grass.
+	#[serde(default)]
+	pub replaceable: bool
+}
+
+fn opaque() -> u8 {
+	15
+}
+
+fn yes() -> bool {
+	true
+}
+
+impl Default for BlockProperties {
+	/// The properties of a normal, opaque, solid block like stone.
+	fn default() -> Self {
+		BlockProperties {
+			opacity: 15,
+			emission: 0,
+			solid: true,
+			liquid: None,
+			replaceable: false
+		}
+	}
+}
+
+/// Maps block types to their properties. Blocks that have not been registered use the default properties.
+#[derive(Debug, Clone)]
+pub struct BlockRegistry<B> where B: Target {
+	default: BlockProperties,
+	properties: HashMap<B, BlockProperties>
+}
+
+impl<B> BlockRegistry<B> where B: Target {
+	pub fn new(default: BlockProperties) -> Self {
+		BlockRegistry {
+			default,
+			properties: HashMap::new()
+		}
+	}
+
+	pub fn set(&mut self, block: B, properties: BlockProperties) {
+		self.properties.insert(block, properties);
+	}
+
+	pub fn get(&self, block: &B) -> &BlockProperties {
+		self.properties.get(block).unwrap_or(&self.default)
+	}
+
+	pub fn default_properties(&self) -> &BlockProperties {
+		&self.default
+	}
+
+	/// Creates a matcher that matches every block whose properties satisfy the predicate, including unregistered blocks.
+	pub fn matcher<F>(&self, predicate: F) -> BlockMatcher<B> where F: Fn(&BlockProperties) -> bool {
+		let blacklist = predicate(&self.default);
+
+		// If the default properties match, then unregistered blocks match too, so list the exceptions instead.
+		let blocks = self.properties.iter()
+			.filter(|&(_, properties)| predicate(properties) != blacklist)
+			.map(|(block, _)| block);
+
+		if blacklist {
+			BlockMatcher::exclude(blocks)
+		} else {
+			BlockMatcher::include(blocks)
+		}
+	}
+
+	pub fn solid(&self) -> BlockMatcher<B> {
+		self.matcher(|properties| properties.solid)
+	}
+
+	pub fn liquid(&self) -> BlockMatcher<B> {
+		self.matcher(|properties| properties.liquid.is_some())
+	}
+
+	pub fn liquid_of(&self, kind: Liquid) -> BlockMatcher<B> {
+		self.matcher(|properties| properties.liquid == Some(kind))
+	}
+
+	pub fn replaceable(&self) -> BlockMatcher<B> {
+		self.matcher(|properties| properties.replaceable)
+	}
+
+	/// Matches blocks that are not opaque cubes, such as air, plants, and leaves. Notchian trees replace these with foliage.
+	pub fn not_opaque(&self) -> BlockMatcher<B> {
+		self.matcher(|properties| !(properties.solid && properties.opacity >= 15))
+	}
+}
+
+impl BlockRegistry<u16> {
+	/// Builds a lookup table of the opacity of each block, as used by the lighting engine.
+	pub fn opacity(&self) -> SparseStorage<u4> {
+		self.nibbles(|properties| properties.opacity)
+	}
+
+	/// Builds a lookup table of the light emitted by each block, as used by the lighting engine.
+	pub fn emission(&self) -> SparseStorage<u4> {
+		self.nibbles(|properties| properties.emission)
+	}
+
+	fn nibbles<F>(&self, property: F) -> SparseStorage<u4> where F: Fn(&BlockProperties) -> u8 {
+		let mut storage = SparseStorage::<u4>::with_default(u4::new(min(property(&self.default), 15)));
+
+		for (&block, properties) in &self.properties {
+			storage.set(block as usize, u4::new(min(property(properties), 15)));
+		}
+
+		storage
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{BlockRegistry, BlockProperties, Liquid};
+
+	#[test]
+	fn test_matchers() {
+		let mut registry = BlockRegistry::<u16>::new(BlockProperties::default());
+
+		registry.set(0, BlockProperties { opacity: 0, solid: false, replaceable: true, .. BlockProperties::default() });
+		registry.set(9 * 16, BlockProperties { opacity: 3, solid: false, liquid: Some(Liquid::Water), replaceable: true, .. BlockProperties::default() });
+		registry.set(11 * 16, BlockProperties { solid: false, liquid: Some(Liquid::Lava), emission: 15, replaceable: true, .. BlockProperties::default() });
+
+		let solid = registry.solid();
+		assert!(solid.matches(&(1 * 16)));
+		assert!(!solid.matches(&0));
+		assert!(!solid.matches(&(9 * 16)));
+
+		let water = registry.liquid_of(Liquid::Water);
+		assert!(water.matches(&(9 * 16)));
+		assert!(!water.matches(&(11 * 16)));
+		assert!(!water.matches(&(1 * 16)));
+
+		assert!(registry.liquid().matches(&(11 * 16)));
+		assert_eq!(registry.get(&(11 * 16)).emission, 15);
+		assert_eq!(registry.get(&(1 * 16)).opacity, 15);
+	}
+}
\ No newline at end of file
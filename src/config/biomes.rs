@@ -4,12 +4,20 @@ use distribution::{Chance, Baseline};
 use std::collections::HashMap;
 use std::num::ParseIntError;
 use std::borrow::Cow;
-use decorator::{Dispatcher, DecoratorFactory};
+use decorator::{Dispatcher, MultiDispatcher, Choice, Selection, SelectionOrder, Decorator, DecoratorFactory, Dispatch};
+use decorator::biome::BiomePopulator;
+use decorator::condition::Condition;
+use decorator::height::{Height, Heightmap, HeightmapKind};
+use decorator::overworld_173::Vegetation;
+use block::BlockRegistry;
+use biome::source::BiomeSource;
 
 #[derive(Debug)]
 pub enum Error {
 	ParseInt(ParseIntError),
-	UnknownBiome(String)
+	UnknownBiome(String),
+	UnknownDecoratorSet(String),
+	Decorator(String)
 }
 
 impl From<ParseIntError> for Error {
@@ -21,7 +29,7 @@ impl From<ParseIntError> for Error {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BiomesConfig {
 	#[serde(default)]
-	pub decorator_sets: HashMap<String, Vec<DecoratorConfig>>,
+	pub decorator_sets: HashMap<String, Vec<DispatcherConfig>>,
 	pub biomes: HashMap<String, BiomeConfig>,
 	pub default: String,
 	pub grid: Vec<RectConfig>
@@ -47,21 +55,64 @@ impl BiomesConfig {
 		
 		Ok(grid)
 	}
+	
+	/// Builds a populator that runs the decorator sets of each biome. Only the sets that are used by a biome are built.
+	/// Heightmaps used by the decorators are built from the properties in `blocks`.
+	pub fn to_populator(&self, biomes: BiomeSource<u16>, registry: &HashMap<String, Box<DecoratorFactory<u16>>>, blocks: &BlockRegistry<u16>) -> Result<BiomePopulator<u16>, Error> {
+		let mut populator = BiomePopulator::new(biomes);
+		let mut built = HashMap::new();
+		
+		for biome in self.biomes.values() {
+			for name in &biome.decorators {
+				let set = match built.get(name) {
+					Some(&set) => set,
+					None => {
+						let configs = self.decorator_sets.get(name).ok_or_else(|| Error::UnknownDecoratorSet(name.clone()))?;
+						
+						let dispatchers = configs.iter()
+							.map(|config| config.clone().into_dispatch(registry, blocks))
+							.collect::<Result<Vec<_>, String>>()
+							.map_err(Error::Decorator)?;
+						
+						let set = populator.add_set(dispatchers);
+						built.insert(name.clone(), set);
+						
+						set
+					}
+				};
+				
+				populator.add_to_biome(&biome.debug_name, set);
+			}
+		}
+		
+		Ok(populator)
+	}
+	
+	/// Returns the vegetation of each biome, keyed by the biome name.
+	pub fn vegetation(&self) -> HashMap<String, Vegetation> {
+		self.biomes.values().map(|biome| (biome.debug_name.clone(), biome.vegetation.clone())).collect()
+	}
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BiomeConfig {
+	pub id: u8,
 	pub debug_name: String,
 	pub surface: SurfaceConfig,
+	/// Names of the decorator sets that are run in this biome, in order.
+	#[serde(default)]
+	pub decorators: Vec<String>,
+	/// Trees, flowers, grass, and cacti placed by the built-in Beta decorators.
 	#[serde(default)]
-	pub decorators: Vec<String>
+	pub vegetation: Vegetation
 }
 
 impl BiomeConfig {
 	pub fn to_biome(&self) -> Result<Biome<u16>, ParseIntError> {
 		Ok(Biome {
 			name: Cow::Owned(self.debug_name.clone()),
-			surface: self.surface.to_surface()?
+			surface: self.surface.to_surface()?,
+			id: self.id
 		})
 	}
 }
@@ -98,28 +149,140 @@ impl FollowupConfig {
 	}
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Either a single decorator, or several decorators where one is picked for each attempt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DispatcherConfig {
+	Single(DecoratorConfig),
+	Multi(MultiDecoratorConfig)
+}
+
+impl DispatcherConfig {
+	pub fn into_dispatch(self, registry: &HashMap<String, Box<DecoratorFactory<u16>>>, blocks: &BlockRegistry<u16>) -> Result<Box<Dispatch<u16>>, String> {
+		let dispatch: Box<Dispatch<u16>> = match self {
+			DispatcherConfig::Single(config) => Box::new(config.into_dispatcher(registry, blocks)?),
+			DispatcherConfig::Multi(config)  => Box::new(config.into_dispatcher(registry, blocks)?)
+		};
+		
+		Ok(dispatch)
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DecoratorConfig {
 	pub decorator: String,
 	pub settings: serde_json::Value,
-	pub height_distribution: Chance<Baseline>,
-	pub count: Chance<Baseline>
+	pub height_distribution: HeightConfig,
+	pub count: Chance<Baseline>,
+	#[serde(default)]
+	pub conditions: Vec<Condition<u16>>
 }
 
 impl DecoratorConfig {
-	pub fn into_dispatcher(self, registry: &HashMap<String, Box<DecoratorFactory<u16>>>) -> Result<Dispatcher<Chance<Baseline>, Chance<Baseline>, u16>, String> {
-		let factory = registry.get(&self.decorator).ok_or_else(|| format!("unknown decorator kind: {}", self.decorator))?;
-
-		let decorator =factory.configure(self.settings).map_err(|e| format!("{}", e))?;
+	pub fn into_dispatcher(self, registry: &HashMap<String, Box<DecoratorFactory<u16>>>, blocks: &BlockRegistry<u16>) -> Result<Dispatcher<Height<u16>, Chance<Baseline>, u16>, String> {
+		let decorator = configure(registry, &self.decorator, self.settings)?;
 
 		Ok(Dispatcher {
 			decorator,
-			height_distribution: self.height_distribution,
-			rarity: self.count
+			height_distribution: self.height_distribution.to_height(blocks),
+			rarity: self.count,
+			conditions: self.conditions
 		})
 	}
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiDecoratorConfig {
+	#[serde(default)]
+	pub selection: Selection,
+	#[serde(default)]
+	pub order: SelectionOrder,
+	pub choices: Vec<ChoiceConfig>,
+	pub height_distribution: HeightConfig,
+	pub count: Chance<Baseline>,
+	#[serde(default)]
+	pub conditions: Vec<Condition<u16>>
+}
+
+impl MultiDecoratorConfig {
+	pub fn into_dispatcher(self, registry: &HashMap<String, Box<DecoratorFactory<u16>>>, blocks: &BlockRegistry<u16>) -> Result<MultiDispatcher<Height<u16>, Chance<Baseline>, u16>, String> {
+		if self.choices.is_empty() {
+			return Err("a multi decorator needs at least one choice".to_string());
+		}
+		
+		if self.selection == Selection::Weighted && self.choices.iter().all(|choice| choice.weight == 0) {
+			return Err("a weighted multi decorator needs at least one choice with a non-zero weight".to_string());
+		}
+		
+		let choices = self.choices.into_iter()
+			.map(|choice| Ok(Choice {
+				weight: choice.weight,
+				chance: choice.chance,
+				decorator: configure(registry, &choice.decorator, choice.settings)?
+			}))
+			.collect::<Result<Vec<_>, String>>()?;
+		
+		Ok(MultiDispatcher {
+			height_distribution: self.height_distribution.to_height(blocks),
+			rarity: self.count,
+			selection: self.selection,
+			order: self.order,
+			conditions: self.conditions,
+			choices
+		})
+	}
+}
+
+/// Either a plain distribution, or a heightmap that places decorators on top of the terrain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HeightConfig {
+	Heightmap(HeightmapConfig),
+	Distribution(Chance<Baseline>)
+}
+
+impl HeightConfig {
+	pub fn to_height(&self, blocks: &BlockRegistry<u16>) -> Height<u16> {
+		match *self {
+			HeightConfig::Heightmap(ref config) => Height::Heightmap(Heightmap {
+				offset: config.offset,
+				.. Heightmap::new(config.heightmap, blocks)
+			}),
+			HeightConfig::Distribution(ref distribution) => Height::Distribution(distribution.clone())
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeightmapConfig {
+	pub heightmap: HeightmapKind,
+	/// Added to the height, such as -1 to replace the top block instead of placing on top of it.
+	#[serde(default)]
+	pub offset: i32
+}
+
+fn one() -> u32 {
+	1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChoiceConfig {
+	pub decorator: String,
+	pub settings: serde_json::Value,
+	/// Weight of this choice when using weighted selection.
+	#[serde(default = "one")]
+	pub weight: u32,
+	/// Chance of this choice when using ordered selection, represented as probability = 1 / chance.
+	#[serde(default = "one")]
+	pub chance: u32
+}
+
+fn configure(registry: &HashMap<String, Box<DecoratorFactory<u16>>>, name: &str, settings: serde_json::Value) -> Result<Box<Decorator<u16>>, String> {
+	let factory = registry.get(name).ok_or_else(|| format!("unknown decorator kind: {}", name))?;
+
+	factory.configure(settings).map_err(|e| format!("{}", e))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RectConfig {
 	pub temperature: (f64, f64),
@@ -139,4 +302,134 @@ pub fn parse_id(id: &str) -> Result<u16, ParseIntError> {
 	};
 	
 	Ok(primary * 16 + secondary)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+	use super::*;
+	use biome::Lookup;
+	use biome::climate::{ClimateSource, ClimateSettings};
+	use block::BlockProperties;
+	
+	#[test]
+	fn test_multi_config() {
+		let json = r#"{
+			"selection": "Ordered",
+			"choices": [
+				{ "decorator": "large_tree", "settings": {}, "chance": 10 },
+				{ "decorator": "tree", "settings": {} }
+			],
+			"height_distribution": { "base": { "kind": "Constant", "value": 64 } },
+			"count": { "base": { "kind": "Constant", "value": 1 } }
+		}"#;
+		
+		match serde_json::from_str::<DispatcherConfig>(json).unwrap() {
+			DispatcherConfig::Multi(config) => {
+				assert_eq!(config.selection, Selection::Ordered);
+				assert_eq!(config.order, SelectionOrder::AfterPosition);
+				assert_eq!(config.choices[0].chance, 10);
+				assert_eq!(config.choices[1].chance, 1);
+				assert_eq!(config.choices[1].weight, 1);
+			},
+			DispatcherConfig::Single(_) => panic!("expected a multi decorator config")
+		}
+	}
+	
+	#[test]
+	fn test_conditions_config() {
+		let json = r#"{
+			"decorator": "lake",
+			"settings": {},
+			"height_distribution": { "base": { "kind": "Constant", "value": 64 } },
+			"count": { "base": { "kind": "Constant", "value": 1 } },
+			"conditions": [
+				{ "kind": "Any", "conditions": [
+					{ "kind": "Below", "y": 64 },
+					{ "kind": "Chance", "chance": 10 }
+				]},
+				{ "kind": "Block", "offset": [0, -1, 0], "matcher": { "blacklist": false, "blocks": [16] } }
+			]
+		}"#;
+		
+		match serde_json::from_str::<DispatcherConfig>(json).unwrap() {
+			DispatcherConfig::Single(config) => {
+				assert_eq!(config.conditions.len(), 2);
+				
+				match config.conditions[0] {
+					Condition::Any { ref conditions } => assert_eq!(conditions.len(), 2),
+					_ => panic!("expected an Any condition")
+				}
+				
+				match config.conditions[1] {
+					Condition::Block { offset, ref matcher } => {
+						assert_eq!(offset, (0, -1, 0));
+						assert!(matcher.matches(&16));
+					},
+					_ => panic!("expected a Block condition")
+				}
+			},
+			DispatcherConfig::Multi(_) => panic!("expected a single decorator config")
+		}
+	}
+	
+	#[test]
+	fn test_height_config() {
+		let heightmap = serde_json::from_str::<HeightConfig>(r#"{ "heightmap": "OceanFloor", "offset": -1 }"#).unwrap();
+		
+		match heightmap {
+			HeightConfig::Heightmap(config) => {
+				assert_eq!(config.heightmap, HeightmapKind::OceanFloor);
+				assert_eq!(config.offset, -1);
+			},
+			HeightConfig::Distribution(_) => panic!("expected a heightmap")
+		}
+		
+		let distribution = serde_json::from_str::<HeightConfig>(r#"{ "base": { "kind": "Constant", "value": 64 } }"#).unwrap();
+		
+		match distribution {
+			HeightConfig::Distribution(_) => (),
+			HeightConfig::Heightmap(_) => panic!("expected a distribution")
+		}
+	}
+	
+	#[test]
+	fn test_to_populator() {
+		let vein = r#"{
+			"decorator": "vein",
+			"settings": { "blocks": { "replace": { "blacklist": false, "blocks": [16] }, "block": 256 }, "size": 8 },
+			"height_distribution": { "base": { "kind": "Constant", "value": 32 } },
+			"count": { "base": { "kind": "Constant", "value": 1 } }
+		}"#;
+		
+		let json = format!(r#"{{
+			"biomes": {{
+				"plains": {{ "id": 1, "debug_name": "Plains", "surface": {{ "top": "2:0",  "fill": "3:0",  "chain": [] }}, "decorators": ["ores", "flowers"] }},
+				"desert": {{ "id": 2, "debug_name": "Desert", "surface": {{ "top": "12:0", "fill": "12:0", "chain": [] }}, "decorators": ["ores"] }},
+				"forest": {{ "id": 4, "debug_name": "Forest", "surface": {{ "top": "2:0",  "fill": "3:0",  "chain": [] }} }}
+			}},
+			"default": "plains",
+			"grid": [],
+			"decorator_sets": {{
+				"ores": [{vein}, {vein}],
+				"flowers": [{vein}],
+				"unused": [{{ "decorator": "missing", "settings": {{}}, "height_distribution": {{ "base": {{ "kind": "Constant", "value": 0 }} }}, "count": {{ "base": {{ "kind": "Constant", "value": 1 }} }} }}]
+			}}
+		}}"#, vein = vein);
+		
+		let config = serde_json::from_str::<BiomesConfig>(&json).unwrap();
+		let biomes = BiomeSource::new(ClimateSource::new(0, ClimateSettings::default()), Lookup::generate(&config.to_grid().unwrap()));
+		let blocks = BlockRegistry::new(BlockProperties::default());
+		
+		// The unused set has an unknown decorator, but is never built.
+		let populator = config.to_populator(biomes, &::decorator::default_registry(&blocks), &blocks).unwrap();
+		
+		let plains = populator.sets("Plains");
+		assert_eq!(plains.iter().map(|set| set.len()).collect::<Vec<_>>(), vec![2, 1]);
+		
+		// Sets used by multiple biomes are only built once.
+		let desert = populator.sets("Desert");
+		assert_eq!(desert.len(), 1);
+		assert_eq!(desert[0].as_ptr(), plains[0].as_ptr());
+		
+		assert!(populator.sets("Forest").is_empty());
+	}
+}
@@ -0,0 +1,41 @@
+use block::{BlockRegistry, BlockProperties};
+use config::biomes::parse_id;
+use std::collections::HashMap;
+use std::num::ParseIntError;
+
+/// Properties of every block type, where each key is either a single block (`"8:0"`, or `"8"` for metadata 0)
+/// or every metadata value of a block ID (`"8:*"`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlocksConfig {
+	#[serde(default)]
+	pub default: BlockProperties,
+	pub blocks: HashMap<String, BlockProperties>
+}
+
+impl BlocksConfig {
+	pub fn to_registry(&self) -> Result<BlockRegistry<u16>, ParseIntError> {
+		let mut registry = BlockRegistry::new(self.default);
+
+		// Apply the wildcards first, so that specific metadata values can override them.
+		for wildcards in &[true, false] {
+			for (id, properties) in self.blocks.iter().filter(|&(id, _)| id.ends_with(":*") == *wildcards) {
+				for block in parse_ids(id)? {
+					registry.set(block, *properties);
+				}
+			}
+		}
+
+		Ok(registry)
+	}
+}
+
+/// Parses a block in the same format as `parse_id`, with the addition of `<id>:*` matching all 16 metadata values.
+pub fn parse_ids(id: &str) -> Result<Vec<u16>, ParseIntError> {
+	if id.ends_with(":*") {
+		let primary = id[..id.len() - 2].parse::<u16>()?;
+
+		Ok((0..16).map(|meta| primary * 16 + meta).collect())
+	} else {
+		Ok(vec![parse_id(id)?])
+	}
+}
\ No newline at end of file
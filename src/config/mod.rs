@@ -1,2 +1,4 @@
 pub mod biomes;
-pub mod settings;
\ No newline at end of file
+pub mod blocks;
+pub mod settings;
+pub mod profile;
\ No newline at end of file
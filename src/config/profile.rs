@@ -0,0 +1,177 @@
+use config::settings::customized::{Customized, Parts};
+use config::biomes::{self, BiomesConfig};
+use config::blocks::BlocksConfig;
+use generator::Pass;
+use generator::overworld_173::{self, Settings};
+use biome::Lookup;
+use biome::source::BiomeSource;
+use biome::climate::ClimateSource;
+use decorator::{self, Populator};
+use decorator::overworld_173::{OverworldPopulator, Features};
+use decorator::dungeon::loot::{LootTable, LootError};
+use structure::StructureGenerateNearby;
+use structure::caves::CavesGenerator;
+use matcher::BlockMatcher;
+use block::{BlockRegistry, Liquid};
+use world::WorldGenerator;
+use serde_json;
+use std::path::Path;
+use std::collections::HashMap;
+use std::fs::File;
+use std::cmp::min;
+use std::num::ParseIntError;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+	Io(io::Error),
+	Json(serde_json::Error),
+	Biomes(biomes::Error),
+	ParseInt(ParseIntError),
+	/// A table in `loot.json` is invalid, along with the name of the table.
+	Loot(String, LootError),
+	/// A decorator chance in `customized.json` is below the minimum that the decorator supports.
+	InvalidChance { name: &'static str, value: i32, min: i32 }
+}
+
+impl From<io::Error> for Error {
+	fn from(from: io::Error) -> Self {
+		Error::Io(from)
+	}
+}
+
+impl From<serde_json::Error> for Error {
+	fn from(from: serde_json::Error) -> Self {
+		Error::Json(from)
+	}
+}
+
+impl From<biomes::Error> for Error {
+	fn from(from: biomes::Error) -> Self {
+		Error::Biomes(from)
+	}
+}
+
+impl From<ParseIntError> for Error {
+	fn from(from: ParseIntError) -> Self {
+		Error::ParseInt(from)
+	}
+}
+
+/// A generation profile, loaded from a directory containing a `customized.json`, a `biomes.json`, and a `blocks.json`.
+/// The directory may also contain a `loot.json` with named loot tables, where the `dungeon` table replaces the
+/// default loot of dungeon chests.
+pub struct Profile {
+	pub parts: Parts,
+	pub biomes: BiomesConfig,
+	pub blocks: BlockRegistry<u16>,
+	pub loot: HashMap<String, LootTable>
+}
+
+impl Profile {
+	pub fn load(path: &Path) -> Result<Self, Error> {
+		let customized = serde_json::from_reader::<File, Customized>(File::open(path.join("customized.json"))?)?;
+		let biomes = serde_json::from_reader::<File, BiomesConfig>(File::open(path.join("biomes.json"))?)?;
+		let blocks = serde_json::from_reader::<File, BlocksConfig>(File::open(path.join("blocks.json"))?)?;
+
+		let loot_path = path.join("loot.json");
+		let loot = if loot_path.exists() {
+			serde_json::from_reader::<File, HashMap<String, LootTable>>(File::open(loot_path)?)?
+		} else {
+			HashMap::new()
+		};
+
+		for (name, table) in &loot {
+			table.validate().map_err(|error| Error::Loot(name.clone(), error))?;
+		}
+
+		Ok(Profile {
+			parts: Parts::from(customized),
+			biomes,
+			blocks: blocks.to_registry()?,
+			loot
+		})
+	}
+
+	/// Builds the Beta 1.7.3 style overworld pipeline described by this profile.
+	/// Population runs the built-in Beta decorators first, followed by the decorator sets of each biome in `biomes.json`.
+	pub fn into_overworld(self, seed: u64) -> Result<WorldGenerator<u16>, Error> {
+		let parts = self.parts;
+		let mut settings = Settings::default();
+
+		settings.tri = parts.tri;
+		settings.height = parts.height.into();
+		settings.field.height_stretch = parts.height_stretch;
+
+		// TODO: Biome Settings
+
+		let sea_block = if parts.ocean.top > 0 {
+			settings.sea_coord = min(parts.ocean.top - 1, 255) as u8;
+
+			if parts.ocean.lava { 11*16 } else { 9*16 }
+		} else {
+			0*16
+		};
+
+		settings.shape_blocks.ocean = sea_block;
+		settings.paint_blocks.ocean = sea_block;
+
+		// TODO: Structures
+
+		let grid = self.biomes.to_grid()?;
+
+		let biomes = BiomeSource::new(ClimateSource::new(seed, settings.climate), Lookup::generate(&grid));
+
+		let populator = OverworldPopulator::new(
+			seed,
+			biomes.clone(),
+			&self.blocks,
+			self.biomes.vegetation(),
+			Features {
+				water_lake_chance: chance("waterLakeChance", parts.decorators.water_lake_chance, 1)?,
+				lava_lake_chance:  chance("lavaLakeChance",  parts.decorators.lava_lake_chance, 10)?,
+				dungeon_chance:    chance("dungeonChance",   parts.decorators.dungeon_chance,    0)?
+			},
+			self.loot.get("dungeon").cloned().unwrap_or_else(LootTable::beta_dungeon)
+		);
+
+		let biome_populator = self.biomes.to_populator(
+			biomes.clone(),
+			&decorator::default_registry(&self.blocks),
+			&self.blocks
+		)?;
+
+		let populators: Vec<Box<Populator<u16>>> = vec![Box::new(populator), Box::new(biome_populator)];
+
+		let (shape, paint) = overworld_173::passes(seed, settings, biomes.clone());
+
+		let mut terrain: Vec<Box<Pass<u16>>> = vec![Box::new(shape), Box::new(paint)];
+
+		if parts.structures.caves {
+			let caves_generator = CavesGenerator {
+				carve: 0*16,
+				lower: 10*16,
+				surface_block: 2*16,
+				ocean: self.blocks.liquid_of(Liquid::Water),
+				carvable: BlockMatcher::include([1*16, 2*16, 3*16].iter()),
+				surface_top: BlockMatcher::is(2*16),
+				surface_fill: BlockMatcher::is(3*16),
+				blob_size_multiplier: 1.0,
+				vertical_multiplier: 1.0,
+				lower_surface: 10
+			};
+
+			terrain.push(Box::new(StructureGenerateNearby::new(seed, 8, caves_generator)));
+		}
+
+		Ok(WorldGenerator::new(seed, 0*16, terrain, Box::new(populators), biomes))
+	}
+}
+
+/// Converts a decorator chance from `customized.json`, rejecting values below `min`.
+fn chance(name: &'static str, chance: Option<i32>, min: i32) -> Result<Option<u32>, Error> {
+	match chance {
+		Some(value) if value < min => Err(Error::InvalidChance { name, value, min }),
+		chance => Ok(chance.map(|value| value as u32))
+	}
+}
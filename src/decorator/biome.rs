@@ -0,0 +1,66 @@
+use java_rand::Random;
+use vocs::indexed::Target;
+use decorator::quad::Quad;
+use vocs::position::GlobalColumnPosition;
+use biome::source::BiomeSource;
+use decorator::{Context, Dispatch, Populator, Result};
+use std::collections::HashMap;
+
+/// Runs the decorator sets of the biome that each chunk is in. Like the Notchian generator, the biome of a chunk
+/// is taken from the block at the +XZ corner of the quad.
+pub struct BiomePopulator<B> where B: Target {
+	biomes: BiomeSource<B>,
+	sets: Vec<Vec<Box<Dispatch<B>>>>,
+	/// Indices into `sets` for each biome, keyed by the biome name.
+	biome_sets: HashMap<String, Vec<usize>>
+}
+
+impl<B> BiomePopulator<B> where B: Target {
+	pub fn new(biomes: BiomeSource<B>) -> Self {
+		BiomePopulator {
+			biomes,
+			sets: Vec::new(),
+			biome_sets: HashMap::new()
+		}
+	}
+
+	/// Adds a decorator set, returning the index used to refer to it in `add_to_biome`.
+	pub fn add_set(&mut self, set: Vec<Box<Dispatch<B>>>) -> usize {
+		self.sets.push(set);
+
+		self.sets.len() - 1
+	}
+
+	/// Appends a decorator set to the sets run for a biome. Sets run in the order that they were added to the biome.
+	pub fn add_to_biome(&mut self, biome: &str, set: usize) {
+		assert!(set < self.sets.len(), "decorator set {} does not exist", set);
+
+		self.biome_sets.entry(biome.to_string()).or_insert_with(Vec::new).push(set);
+	}
+
+	/// Returns the decorator sets run for a biome, in order.
+	pub fn sets(&self, biome: &str) -> Vec<&[Box<Dispatch<B>>]> {
+		self.biome_sets.get(biome)
+			.map(|sets| sets.iter().map(|&set| &self.sets[set][..]).collect())
+			.unwrap_or_else(Vec::new)
+	}
+}
+
+impl<B> Populator<B> for BiomePopulator<B> where B: Target {
+	fn populate(&self, quad: &mut Quad<B>, rng: &mut Random, column: GlobalColumnPosition) -> Result {
+		let context = Context::new(column, &self.biomes);
+
+		let sets = match self.biome_sets.get(&*context.biome.name) {
+			Some(sets) => sets,
+			None => return Ok(())
+		};
+
+		for &set in sets {
+			for dispatcher in &self.sets[set] {
+				dispatcher.dispatch(quad, rng, &context)?;
+			}
+		}
+
+		Ok(())
+	}
+}
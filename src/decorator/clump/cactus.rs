@@ -1,17 +1,29 @@
 use vocs::position::{QuadPosition, Offset, dir};
 use vocs::view::QuadMut;
+use decorator::quad::Quad;
 use vocs::indexed::Target;
 use matcher::BlockMatcher;
-use decorator::{Decorator, Result};
+use decorator::{Decorator, DecoratorFactory, Context, Result};
 use java_rand::Random;
+use serde_json;
 
+#[derive(Default)]
+pub struct CactusDecoratorFactory<B>(::std::marker::PhantomData<B>);
+impl<B> DecoratorFactory<B> for CactusDecoratorFactory<B> where B: 'static + Target + ::serde::Deserialize {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		Ok(Box::new(serde_json::from_value::<CactusDecorator<B>>(config)?))
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CactusDecorator<B> where B: Target {
 	pub blocks: CactusBlocks<B>,
+	#[serde(default)]
 	pub settings: CactusSettings
 }
 
 impl<B> Decorator<B> for CactusDecorator<B> where B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, rng: &mut Random, position: QuadPosition) -> Result {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
 		if !self.blocks.replace.matches(quad.get(position)) {
 			return Ok(());
 		}
@@ -22,20 +34,21 @@ impl<B> Decorator<B> for CactusDecorator<B> where B: Target {
 		let mut position = position;
 
 		for _ in 0..height {
+			if self.blocks.check(quad, position) {
+				quad.set_immediate(position, &self.blocks.block);
+			}
+
 			position = match position.offset(dir::Up) {
 				Some(position) => position,
 				None => return Ok(())
 			};
-
-			if self.blocks.check(quad, position) {
-				quad.set_immediate(position, &self.blocks.block);
-			}
 		}
 
 		Ok(())
 	}
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CactusBlocks<B> where B: Target {
 	pub replace: BlockMatcher<B>, // Air
 	pub base: BlockMatcher<B>, // Cactus / Sand
@@ -82,6 +95,7 @@ impl<B> CactusBlocks<B> where B: Target {
 	}
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CactusSettings {
 	/// Base, minimum height of a cactus
 	pub base_height: u32,
@@ -1,17 +1,65 @@
 use java_rand::Random;
 use vocs::indexed::Target;
-use vocs::view::QuadMut;
-use vocs::position::{QuadPosition, Offset};
-use super::{Decorator, Result};
+use decorator::quad::Quad;
+use vocs::position::QuadPosition;
+use super::{Decorator, DecoratorFactory, Context, Result};
+use serde_json;
 
 pub mod cactus;
 pub mod plant;
 pub mod sugar_cane;
 
+/// Configures a `Clump` of the decorator created by the wrapped factory from the `decorator` field.
+pub struct ClumpDecoratorFactory<F, B>(pub F, pub ::std::marker::PhantomData<B>) where F: DecoratorFactory<B>, B: Target;
+impl<F, B> DecoratorFactory<B> for ClumpDecoratorFactory<F, B> where F: DecoratorFactory<B>, B: 'static + Target {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		let config = serde_json::from_value::<ClumpConfig>(config)?;
+
+		Ok(Box::new(Clump {
+			iterations: config.iterations,
+			horizontal: config.horizontal,
+			vertical: config.vertical,
+			decorator: self.0.configure(config.decorator)?,
+			phantom: ::std::marker::PhantomData
+		}))
+	}
+}
+
+/// Configures a `FlatClump` of the decorator created by the wrapped factory from the `decorator` field.
+pub struct FlatClumpDecoratorFactory<F, B>(pub F, pub ::std::marker::PhantomData<B>) where F: DecoratorFactory<B>, B: Target;
+impl<F, B> DecoratorFactory<B> for FlatClumpDecoratorFactory<F, B> where F: DecoratorFactory<B>, B: 'static + Target {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		let config = serde_json::from_value::<FlatClumpConfig>(config)?;
+
+		Ok(Box::new(FlatClump {
+			iterations: config.iterations,
+			horizontal: config.horizontal,
+			decorator: self.0.configure(config.decorator)?,
+			phantom: ::std::marker::PhantomData
+		}))
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ClumpConfig {
+	iterations: u32,
+	horizontal: u8,
+	vertical: u8,
+	decorator: serde_json::Value
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct FlatClumpConfig {
+	iterations: u32,
+	horizontal: u8,
+	decorator: serde_json::Value
+}
+
 /// Clumped generation. Places a number of objects with a varying distance from the center.
 pub struct Clump<D, B> where D: Decorator<B>, B: Target {
 	pub iterations: u32,
-	/// Horizontal variance. Must be 8 or below, or else spilling will occur.
+	/// Horizontal variance. Attempts outside of the quad are skipped and counted with `Quad::skip`, which never happens
+	/// if this is 8 or below.
 	pub horizontal: u8,
 	/// Vertical variance.
 	pub vertical: u8,
@@ -20,7 +68,7 @@ pub struct Clump<D, B> where D: Decorator<B>, B: Target {
 }
 
 impl<D, B> Decorator<B> for Clump<D, B> where D: Decorator<B>, B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, rng: &mut Random, position: QuadPosition) -> Result {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, context: &Context<B>) -> Result {
 		for _ in 0..self.iterations {
 			let offset = (
 				rng.next_i32_bound(self.horizontal as i32) - rng.next_i32_bound(self.horizontal as i32),
@@ -28,22 +76,18 @@ impl<D, B> Decorator<B> for Clump<D, B> where D: Decorator<B>, B: Target {
 				rng.next_i32_bound(self.horizontal as i32) - rng.next_i32_bound(self.horizontal as i32)
 			);
 
-			if (position.y() as i32) + offset.1 < 0 {
-				continue;
-			}
-
-			let at = match position.offset ((
-				offset.0 as i8,
-				offset.1 as i8,
-				offset.2 as i8
-			)) {
+			let at = match offset_within(position, offset) {
 				Some(at) => at,
+				// The wrapped decorator needs to read the blocks around the position, which do not exist outside of
+				// the quad. Unlike plain blocks, these attempts cannot be spilled, so they are skipped. This also skips
+				// the RNG calls of the wrapped decorator, so the rest of the quad no longer matches the Notchian output.
 				None => {
-					panic!("out of bounds offsetting {:?} by {:?}", position, offset);
+					quad.skip();
+					continue;
 				}
 			};
-			
-			self.decorator.generate(quad, rng, at)?;
+
+			self.decorator.generate(quad, rng, at, context)?;
 		}
 
 		Ok(())
@@ -52,29 +96,102 @@ impl<D, B> Decorator<B> for Clump<D, B> where D: Decorator<B>, B: Target {
 
 pub struct FlatClump<D, B> where D: Decorator<B>, B: Target {
 	pub iterations: u32,
-	/// Horizontal variance. Must be 8 or below, or else spilling will occur.
+	/// Horizontal variance. Attempts outside of the quad are skipped and counted with `Quad::skip`, which never happens
+	/// if this is 8 or below.
 	pub horizontal: u8,
 	pub decorator: D,
 	pub phantom: ::std::marker::PhantomData<B>
 }
 
 impl<D, B> Decorator<B> for FlatClump<D, B> where D: Decorator<B>, B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, rng: &mut Random, position: QuadPosition) -> Result {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, context: &Context<B>) -> Result {
 		for _ in 0..self.iterations {
 			let offset = (
 				rng.next_i32_bound(self.horizontal as i32) - rng.next_i32_bound(self.horizontal as i32),
 				rng.next_i32_bound(self.horizontal as i32) - rng.next_i32_bound(self.horizontal as i32)
 			);
 
-			let at = position.offset ((
-				offset.0 as i8,
-				0,
-				offset.1 as i8
-			)).unwrap();
+			let at = match offset_within(position, (offset.0, 0, offset.1)) {
+				Some(at) => at,
+				None => {
+					quad.skip();
+					continue;
+				}
+			};
 
-			self.decorator.generate(quad, rng, at)?;
+			self.decorator.generate(quad, rng, at, context)?;
 		}
 
 		Ok(())
 	}
-}
\ No newline at end of file
+}
+
+/// Offsets the position, returning None if the result is outside of the quad.
+fn offset_within(position: QuadPosition, offset: (i32, i32, i32)) -> Option<QuadPosition> {
+	let x = position.x() as i32 + offset.0;
+	let y = position.y() as i32 + offset.1;
+	let z = position.z() as i32 + offset.2;
+
+	if x < 0 || x >= 32 || y < 0 || y >= 256 || z < 0 || z >= 32 {
+		None
+	} else {
+		Some(QuadPosition::new(x as u8, y as u8, z as u8))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Clump, FlatClump};
+	use decorator::{Decorator, Context, Result};
+	use decorator::quad::Quad;
+	use java_rand::Random;
+	use vocs::position::{GlobalColumnPosition, QuadPosition};
+
+	struct Place;
+
+	impl Decorator<u16> for Place {
+		fn generate(&self, quad: &mut Quad<u16>, _: &mut Random, position: QuadPosition, _: &Context<u16>) -> Result {
+			quad.set_immediate(position, &(1*16));
+
+			Ok(())
+		}
+	}
+
+	/// Counts the blocks placed at Y=64 by the decorator, returning them along with the amount of skipped attempts.
+	fn run<D>(decorator: &D, position: QuadPosition) -> (usize, usize) where D: Decorator<u16> {
+		let mut world = ::test::quad_world(0, 0);
+		let biomes = ::test::plains();
+		let context = Context::new(GlobalColumnPosition::new(0, 0), &biomes);
+		let mut quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		decorator.generate(&mut quad, &mut Random::new(1), position, &context).unwrap();
+
+		let mut placed = 0;
+
+		for x in 0..32 {
+			for z in 0..32 {
+				if *quad.get(QuadPosition::new(x, 64, z)) != 0 {
+					placed += 1;
+				}
+			}
+		}
+
+		(placed, quad.skipped())
+	}
+
+	#[test]
+	fn test_attempts_outside_quad_are_counted() {
+		let clump = Clump { iterations: 64, horizontal: 16, vertical: 1, decorator: Place, phantom: ::std::marker::PhantomData };
+		let flat = FlatClump { iterations: 64, horizontal: 16, decorator: Place, phantom: ::std::marker::PhantomData };
+
+		for &(placed, skipped) in &[run(&clump, QuadPosition::new(2, 64, 2)), run(&flat, QuadPosition::new(2, 64, 2))] {
+			assert!(skipped > 0);
+			assert!(placed > 0);
+			assert!(placed <= 64 - skipped);
+		}
+
+		// Within 8 blocks of the center of the quad, nothing is ever skipped.
+		let clump = Clump { iterations: 64, horizontal: 8, vertical: 1, decorator: Place, phantom: ::std::marker::PhantomData };
+		assert_eq!(run(&clump, QuadPosition::new(16, 64, 16)).1, 0);
+	}
+}
@@ -1,12 +1,24 @@
 use java_rand::Random;
 use vocs::indexed::Target;
-use vocs::view::QuadMut;
+use decorator::quad::Quad;
 use vocs::position::{QuadPosition, Offset, dir};
-use decorator::{Decorator, Result};
+use decorator::{Decorator, DecoratorFactory, Context, Result};
 use matcher::BlockMatcher;
+use serde_json;
 
 // Pumpkin: On grass, replacing air or {material:ground_cover}
 
+#[derive(Default)]
+pub struct PlantDecoratorFactory<B>(::std::marker::PhantomData<B>);
+impl<B> DecoratorFactory<B> for PlantDecoratorFactory<B> where B: 'static + Target + ::serde::Deserialize {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		Ok(Box::new(serde_json::from_value::<PlantDecorator<B>>(config)?))
+	}
+}
+
+/// Places a plant on a base block. To only place plants on the surface, dispatch this with a `Heightmap` height
+/// distribution instead of checking the heightmap here.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PlantDecorator<B> where B: Target {
 	pub block: B,
 	pub base: BlockMatcher<B>,
@@ -14,9 +26,7 @@ pub struct PlantDecorator<B> where B: Target {
 }
 
 impl<B> Decorator<B> for PlantDecorator<B> where B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, _: &mut Random, position: QuadPosition) -> Result {
-		// TODO: Check if the block is above the heightmap (how?)
-
+	fn generate(&self, quad: &mut Quad<B>, _: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
 		if !self.replace.matches(quad.get(position)) {
 			return Ok(());
 		}
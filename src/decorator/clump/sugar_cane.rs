@@ -1,10 +1,20 @@
 use java_rand::Random;
 use vocs::indexed::Target;
-use vocs::view::QuadMut;
+use decorator::quad::Quad;
 use vocs::position::{QuadPosition, Offset, dir};
-use decorator::{Decorator, Result};
+use decorator::{Decorator, DecoratorFactory, Context, Result};
 use matcher::BlockMatcher;
+use serde_json;
 
+#[derive(Default)]
+pub struct SugarCaneDecoratorFactory<B>(::std::marker::PhantomData<B>);
+impl<B> DecoratorFactory<B> for SugarCaneDecoratorFactory<B> where B: 'static + Target + ::serde::Deserialize {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		Ok(Box::new(serde_json::from_value::<SugarCaneDecorator<B>>(config)?))
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SugarCaneDecorator<B> where B: Target {
 	pub block: B,
 	pub base: BlockMatcher<B>,
@@ -15,7 +25,7 @@ pub struct SugarCaneDecorator<B> where B: Target {
 }
 
 impl<B> Decorator<B> for SugarCaneDecorator<B> where B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, rng: &mut Random, position: QuadPosition) -> Result {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
 		if !self.replace.matches(quad.get(position)) {
 			return Ok(());
 		}
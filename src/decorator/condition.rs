@@ -0,0 +1,201 @@
+//! Conditions that decide whether a dispatcher runs its decorator at a position.
+//! Conditions that call the RNG do so in the order they are listed, and `Any`/`All` short circuit like `||` and `&&`,
+//! so that conditions such as `y < 64 || rand(10) == 0` consume the RNG like the Notchian generator.
+
+use java_rand::Random;
+use vocs::indexed::Target;
+use vocs::position::QuadPosition;
+use decorator::Context;
+use decorator::quad::Quad;
+use matcher::BlockMatcher;
+use biome::climate::Climate;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Condition<B> where B: Target {
+	/// Passes if the Y coordinate is below `y`.
+	Below { y: u8 },
+	/// Passes if the Y coordinate is above `y`.
+	Above { y: u8 },
+	/// Passes 1 in `chance` times. A chance of 1 does not call the RNG, and always passes.
+	Chance { chance: u32 },
+	/// Passes if the block at `offset` from the position matches. Blocks outside of the quad never match.
+	Block {
+		#[serde(default)]
+		offset: (i8, i8, i8),
+		matcher: BlockMatcher<B>
+	},
+	/// Passes if the chunk is in one of the biomes, by name. If `site` is set, the biome at the position is used instead.
+	Biome {
+		biomes: Vec<String>,
+		#[serde(default)]
+		site: bool
+	},
+	/// Passes if the temperature of the chunk is between `min` and `max`, inclusive. If `site` is set, the
+	/// temperature at the position is used instead.
+	Temperature {
+		min: f64,
+		max: f64,
+		#[serde(default)]
+		site: bool
+	},
+	/// Passes if the rainfall of the chunk is between `min` and `max`, inclusive. If `site` is set, the
+	/// rainfall at the position is used instead.
+	Rainfall {
+		min: f64,
+		max: f64,
+		#[serde(default)]
+		site: bool
+	},
+	/// Passes if any of the conditions pass. Conditions after the first passing one are not checked.
+	Any { conditions: Vec<Condition<B>> },
+	/// Passes if all of the conditions pass. Conditions after the first failing one are not checked.
+	All { conditions: Vec<Condition<B>> },
+	/// Passes if the condition fails.
+	Not { condition: Box<Condition<B>> }
+}
+
+impl<B> Condition<B> where B: Target {
+	pub fn check(&self, quad: &Quad<B>, rng: &mut Random, position: QuadPosition, context: &Context<B>) -> bool {
+		match *self {
+			Condition::Below { y } => position.y() < y,
+			Condition::Above { y } => position.y() > y,
+			Condition::Chance { chance } => chance <= 1 || rng.next_u32_bound(chance) == 0,
+			Condition::Block { offset, ref matcher } => {
+				let x = position.x() as i32 + offset.0 as i32;
+				let y = position.y() as i32 + offset.1 as i32;
+				let z = position.z() as i32 + offset.2 as i32;
+
+				if x < 0 || x >= 32 || y < 0 || y >= 256 || z < 0 || z >= 32 {
+					return false;
+				}
+
+				matcher.matches(quad.get(QuadPosition::new(x as u8, y as u8, z as u8)))
+			},
+			Condition::Biome { ref biomes, site } => {
+				let biome = if site { context.biome_at(position) } else { context.biome };
+
+				biomes.iter().any(|name| **name == *biome.name)
+			},
+			Condition::Temperature { min, max, site } => {
+				let temperature = climate(position, context, site).temperature();
+
+				temperature >= min && temperature <= max
+			},
+			Condition::Rainfall { min, max, site } => {
+				let rainfall = climate(position, context, site).rainfall();
+
+				rainfall >= min && rainfall <= max
+			},
+			Condition::Any { ref conditions } => conditions.iter().any(|condition| condition.check(quad, rng, position, context)),
+			Condition::All { ref conditions } => conditions.iter().all(|condition| condition.check(quad, rng, position, context)),
+			Condition::Not { ref condition } => !condition.check(quad, rng, position, context)
+		}
+	}
+}
+
+/// Checks a list of conditions in order, stopping at the first one that fails.
+pub fn check_all<B>(conditions: &[Condition<B>], quad: &Quad<B>, rng: &mut Random, position: QuadPosition, context: &Context<B>) -> bool where B: Target {
+	conditions.iter().all(|condition| condition.check(quad, rng, position, context))
+}
+
+fn climate<B>(position: QuadPosition, context: &Context<B>, site: bool) -> Climate where B: Target {
+	if site {
+		context.climate_at(position)
+	} else {
+		context.climate
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Condition;
+	use java_rand::Random;
+	use vocs::position::{GlobalColumnPosition, QuadPosition};
+	use decorator::Context;
+	use decorator::quad::Quad;
+	use matcher::BlockMatcher;
+
+	const SEED: u64 = 1234;
+
+	#[test]
+	fn test_any_short_circuits() {
+		let mut world = ::test::quad_world(1*16, 64);
+		let biomes = ::test::plains();
+		let context = Context::new(GlobalColumnPosition::new(0, 0), &biomes);
+		let quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		// y < 64 || rand(10) == 0
+		let condition = Condition::Any { conditions: vec![Condition::Below { y: 64 }, Condition::Chance { chance: 10 }] };
+
+		for y in 60..68 {
+			let mut rng = Random::new(SEED);
+			let mut expected = Random::new(SEED);
+
+			let passed = condition.check(&quad, &mut rng, QuadPosition::new(8, y, 8), &context);
+
+			if y < 64 {
+				assert!(passed);
+			} else {
+				assert_eq!(passed, expected.next_u32_bound(10) == 0);
+			}
+
+			assert_eq!(rng.next_u64(), expected.next_u64(), "RNG out of sync at Y={}", y);
+		}
+	}
+
+	#[test]
+	fn test_all_and_not_short_circuit() {
+		let mut world = ::test::quad_world(1*16, 64);
+		let biomes = ::test::plains();
+		let context = Context::new(GlobalColumnPosition::new(0, 0), &biomes);
+		let quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		let position = QuadPosition::new(8, 70, 8);
+
+		let all = Condition::All { conditions: vec![Condition::Above { y: 100 }, Condition::Chance { chance: 10 }] };
+		let not_below = Condition::Not { condition: Box::new(Condition::Below { y: 64 }) };
+		let not_always = Condition::Not { condition: Box::new(Condition::Chance { chance: 1 }) };
+
+		let mut rng = Random::new(SEED);
+
+		assert!(!all.check(&quad, &mut rng, position, &context));
+		assert!(not_below.check(&quad, &mut rng, position, &context));
+		assert!(!not_always.check(&quad, &mut rng, position, &context));
+
+		// None of the conditions reached a roll.
+		assert_eq!(rng.next_u64(), Random::new(SEED).next_u64());
+
+		// Once the first condition passes, the roll is made.
+		let all = Condition::All { conditions: vec![Condition::Above { y: 64 }, Condition::Chance { chance: 10 }] };
+		let mut expected = Random::new(SEED);
+
+		assert_eq!(all.check(&quad, &mut rng, position, &context), expected.next_u32_bound(10) == 0);
+		assert_eq!(rng.next_u64(), expected.next_u64());
+	}
+
+	#[test]
+	fn test_block_outside_quad() {
+		let mut world = ::test::quad_world(1*16, 64);
+		let biomes = ::test::plains();
+		let context = Context::new(GlobalColumnPosition::new(0, 0), &biomes);
+		let quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		let mut rng = Random::new(SEED);
+
+		let any_block = |offset: (i8, i8, i8)| -> Condition<u16> { Condition::Block { offset, matcher: BlockMatcher::all() } };
+		let stone = Condition::Block { offset: (0, -1, 0), matcher: BlockMatcher::is(1*16) };
+
+		assert!(stone.check(&quad, &mut rng, QuadPosition::new(0, 64, 0), &context));
+		assert!(!stone.check(&quad, &mut rng, QuadPosition::new(0, 65, 0), &context));
+
+		// Even a matcher that accepts every block does not match outside of the quad.
+		assert!(any_block((0, 0, 0)).check(&quad, &mut rng, QuadPosition::new(0, 64, 0), &context));
+		assert!(!any_block((-1, 0, 0)).check(&quad, &mut rng, QuadPosition::new(0, 64, 0), &context));
+		assert!(!any_block((0, 0, -1)).check(&quad, &mut rng, QuadPosition::new(0, 64, 0), &context));
+		assert!(!any_block((1, 0, 0)).check(&quad, &mut rng, QuadPosition::new(31, 64, 31), &context));
+		assert!(!any_block((0, 0, 1)).check(&quad, &mut rng, QuadPosition::new(31, 64, 31), &context));
+		assert!(!any_block((0, -1, 0)).check(&quad, &mut rng, QuadPosition::new(8, 0, 8), &context));
+		assert!(!any_block((0, 1, 0)).check(&quad, &mut rng, QuadPosition::new(8, 255, 8), &context));
+	}
+}
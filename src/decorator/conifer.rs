@@ -0,0 +1,274 @@
+//! The spruce and pine trees of taiga biomes. Both have a bare trunk at the bottom, and foliage layers that change
+//! in radius from layer to layer, forming the cone shape.
+
+use vocs::indexed::Target;
+use vocs::position::QuadPosition;
+use decorator::quad::Quad;
+use decorator::tree::TreeBlocks;
+use matcher::BlockMatcher;
+use block::BlockRegistry;
+use decorator::{Decorator, DecoratorFactory, Context, Result};
+use java_rand::Random;
+use serde_json;
+
+/// Conifers are not generated if any part of them would be above this Y coordinate, the height of a Beta world.
+const HEIGHT_LIMIT: u32 = 128;
+
+/// Configs that leave out `foliage_replace` get every block of the registry that is not an opaque cube.
+pub struct SpruceDecoratorFactory<B> where B: Target {
+	not_opaque: BlockMatcher<B>
+}
+
+impl<B> SpruceDecoratorFactory<B> where B: Target {
+	pub fn new(blocks: &BlockRegistry<B>) -> Self {
+		SpruceDecoratorFactory {
+			not_opaque: blocks.not_opaque()
+		}
+	}
+}
+
+impl<B> DecoratorFactory<B> for SpruceDecoratorFactory<B> where B: 'static + Target + ::serde::Deserialize {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		let explicit = config.pointer("/blocks/foliage_replace").is_some();
+		let mut decorator = serde_json::from_value::<SpruceDecorator<B>>(config)?;
+
+		if !explicit {
+			decorator.blocks.foliage_replace = self.not_opaque.clone();
+		}
+
+		Ok(Box::new(decorator))
+	}
+}
+
+/// Configs that leave out `foliage_replace` get every block of the registry that is not an opaque cube.
+pub struct PineDecoratorFactory<B> where B: Target {
+	not_opaque: BlockMatcher<B>
+}
+
+impl<B> PineDecoratorFactory<B> where B: Target {
+	pub fn new(blocks: &BlockRegistry<B>) -> Self {
+		PineDecoratorFactory {
+			not_opaque: blocks.not_opaque()
+		}
+	}
+}
+
+impl<B> DecoratorFactory<B> for PineDecoratorFactory<B> where B: 'static + Target + ::serde::Deserialize {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		let explicit = config.pointer("/blocks/foliage_replace").is_some();
+		let mut decorator = serde_json::from_value::<PineDecorator<B>>(config)?;
+
+		if !explicit {
+			decorator.blocks.foliage_replace = self.not_opaque.clone();
+		}
+
+		Ok(Box::new(decorator))
+	}
+}
+
+/// Spruce trees, with foliage layers that repeatedly grow and shrink in radius from the top down.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpruceDecorator<B> where B: Target {
+	pub blocks: TreeBlocks<B>,
+	#[serde(default)]
+	pub settings: SpruceSettings
+}
+
+impl<B> Decorator<B> for SpruceDecorator<B> where B: Target {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
+		let height = self.settings.min_height + rng.next_u32_bound(self.settings.add_height + 1);
+		let bare_trunk = self.settings.min_bare_trunk + rng.next_u32_bound(self.settings.add_bare_trunk + 1);
+		let foliage_height = height - bare_trunk;
+		let max_radius = self.settings.min_radius + rng.next_u32_bound(self.settings.add_radius + 1);
+
+		let base = position.y() as u32;
+
+		if base < 1 || base + height + 1 >= HEIGHT_LIMIT {
+			return Ok(());
+		}
+
+		let bounding_radius = |y: u32| if y - base < bare_trunk { 0 } else { max_radius };
+
+		if !self.blocks.is_clear(quad, position, height, bounding_radius) {
+			return Ok(());
+		}
+
+		if !self.blocks.place_soil(quad, position) {
+			return Ok(());
+		}
+
+		let mut radius = rng.next_u32_bound(2);
+		let mut layer_max_radius = 1;
+		let mut reset_radius = 0;
+
+		for layer in 0..(foliage_height + 1) {
+			foliage_layer(&self.blocks, quad, position, base + height - layer, radius);
+
+			if radius >= layer_max_radius {
+				radius = reset_radius;
+				reset_radius = 1;
+				layer_max_radius = ::std::cmp::min(layer_max_radius + 1, max_radius);
+			} else {
+				radius += 1;
+			}
+		}
+
+		let trunk_shortening = rng.next_u32_bound(self.settings.add_trunk_shortening + 1);
+
+		trunk(&self.blocks, quad, position, height - trunk_shortening);
+
+		Ok(())
+	}
+}
+
+impl Default for SpruceDecorator<u16> {
+	fn default() -> Self {
+		SpruceDecorator {
+			blocks: conifer_blocks(),
+			settings: SpruceSettings::default()
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpruceSettings {
+	pub min_height: u32,
+	pub add_height: u32,
+	/// Height of the part of the trunk without foliage around it.
+	pub min_bare_trunk: u32,
+	pub add_bare_trunk: u32,
+	/// Radius that the foliage layers grow to, once they have grown large enough.
+	pub min_radius: u32,
+	pub add_radius: u32,
+	/// Up to this many blocks are removed from the top of the trunk, leaving foliage at the top without a log.
+	pub add_trunk_shortening: u32
+}
+
+impl Default for SpruceSettings {
+	fn default() -> Self {
+		SpruceSettings {
+			min_height: 6,
+			add_height: 3,
+			min_bare_trunk: 1,
+			add_bare_trunk: 1,
+			min_radius: 2,
+			add_radius: 1,
+			add_trunk_shortening: 2
+		}
+	}
+}
+
+/// Pine trees, with a tall bare trunk and a single cone of foliage at the top.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PineDecorator<B> where B: Target {
+	pub blocks: TreeBlocks<B>,
+	#[serde(default)]
+	pub settings: PineSettings
+}
+
+impl<B> Decorator<B> for PineDecorator<B> where B: Target {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
+		let height = self.settings.min_height + rng.next_u32_bound(self.settings.add_height + 1);
+		let bare_trunk = height - rng.next_u32_bound(self.settings.add_foliage_height + 1) - self.settings.min_foliage_height;
+		let foliage_height = height - bare_trunk;
+		let max_radius = 1 + rng.next_u32_bound(foliage_height + 1);
+
+		let base = position.y() as u32;
+
+		if base < 1 || base + height + 1 >= HEIGHT_LIMIT {
+			return Ok(());
+		}
+
+		let bounding_radius = |y: u32| if y - base < bare_trunk { 0 } else { max_radius };
+
+		if !self.blocks.is_clear(quad, position, height, bounding_radius) {
+			return Ok(());
+		}
+
+		if !self.blocks.place_soil(quad, position) {
+			return Ok(());
+		}
+
+		let mut radius = 0;
+
+		for y in ((base + bare_trunk)..(base + height + 1)).rev() {
+			foliage_layer(&self.blocks, quad, position, y, radius);
+
+			// The cone narrows again for the lowest layer.
+			if radius >= 1 && y == base + bare_trunk + 1 {
+				radius -= 1;
+			} else if radius < max_radius {
+				radius += 1;
+			}
+		}
+
+		trunk(&self.blocks, quad, position, height - 1);
+
+		Ok(())
+	}
+}
+
+impl Default for PineDecorator<u16> {
+	fn default() -> Self {
+		PineDecorator {
+			blocks: conifer_blocks(),
+			settings: PineSettings::default()
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PineSettings {
+	pub min_height: u32,
+	pub add_height: u32,
+	/// Height of the foliage cone. The rest of the trunk is bare.
+	pub min_foliage_height: u32,
+	pub add_foliage_height: u32
+}
+
+impl Default for PineSettings {
+	fn default() -> Self {
+		PineSettings {
+			min_height: 7,
+			add_height: 4,
+			min_foliage_height: 3,
+			add_foliage_height: 1
+		}
+	}
+}
+
+fn conifer_blocks() -> TreeBlocks<u16> {
+	TreeBlocks {
+		log:     17*16 + 1,
+		foliage: 18*16 + 1,
+		.. TreeBlocks::default()
+	}
+}
+
+/// Places a square layer of foliage with the corners cut off, unless the radius is 0.
+/// Foliage outside of the quad is spilled into the neighboring columns.
+fn foliage_layer<B>(blocks: &TreeBlocks<B>, quad: &mut Quad<B>, orgin: QuadPosition, y: u32, radius: u32) where B: Target {
+	let radius = radius as i32;
+
+	for x_offset in -radius..(radius + 1) {
+		for z_offset in -radius..(radius + 1) {
+			if x_offset.abs() == radius && z_offset.abs() == radius && radius > 0 {
+				continue;
+			}
+
+			let position = (orgin.x() as i32 + x_offset, y as i32, orgin.z() as i32 + z_offset);
+
+			quad.set_or_spill(position, &blocks.foliage, &blocks.foliage_replace);
+		}
+	}
+}
+
+fn trunk<B>(blocks: &TreeBlocks<B>, quad: &mut Quad<B>, orgin: QuadPosition, height: u32) where B: Target {
+	for y in (orgin.y() as u32)..((orgin.y() as u32) + height) {
+		let position = QuadPosition::new(orgin.x(), y as u8, orgin.z());
+
+		if blocks.replace.matches(quad.get(position)) {
+			quad.set_immediate(position, &blocks.log);
+		}
+	}
+}
@@ -1,103 +1,202 @@
 use java_rand::Random;
+use entity::tile::ItemStack;
+use item::ItemId;
 
-// TODO
-#[derive(Debug, Copy, Clone)]
-pub enum Item {
-	Saddle,
-	IronIngot,
-	Bread,
-	Wheat,
-	Gunpowder,
-	String,
-	Bucket,
-	GoldenApple,
-	Redstone,
-	GoldRecord,
-	GreenRecord,
-	InkSac
-}
+/// Largest stack size that an entry may produce, the size of a full stack.
+pub const MAX_COUNT: u32 = 64;
 
-#[derive(Debug)]
-pub struct Stack {
-	item: Item,
-	size: u32
+/// Problems with a loot table that would otherwise panic or produce broken item stacks while generating.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LootError {
+	/// The minimum stack size of an entry is larger than the maximum.
+	InvertedCount(Count),
+	/// The stack size of an entry can exceed `MAX_COUNT`.
+	CountTooLarge(Count),
+	/// An entry has a chance of 1 in 0.
+	ZeroChance
 }
 
-pub struct SimpleLootTable {
-	pools: Vec<Pool>
+/// A table of weighted entries, each producing an item stack, a draw from a nested table, or nothing.
+/// Drawing from the table picks an entry using `rand(total_weight)`, so a table where every weight is 1 picks the
+/// entry with `rand(entries)` like the Notchian dungeon loot.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LootTable {
+	/// Number of draws made when filling a container. Nested tables are always drawn from once.
+	#[serde(default="one")]
+	pub rolls: u32,
+	pub entries: Vec<LootEntry>
 }
 
-impl SimpleLootTable {
-	pub fn get_item(&self, rng: &mut Random) -> Option<Stack> {
-		if self.pools.len() != 0 {
-			self.pools[rng.next_u32_bound(self.pools.len() as u32) as usize].get_item(rng)
-		} else {
-			None
+impl LootTable {
+	pub fn get_item(&self, rng: &mut Random) -> Option<ItemStack> {
+		let total = self.entries.iter().map(|entry| entry.weight).sum::<u32>();
+
+		if total == 0 {
+			return None;
+		}
+
+		let mut remaining = rng.next_u32_bound(total);
+
+		for entry in &self.entries {
+			if remaining < entry.weight {
+				return entry.get_item(rng);
+			}
+
+			remaining -= entry.weight;
 		}
+
+		unreachable!()
 	}
-}
 
-impl Default for SimpleLootTable {
-	fn default() -> Self {
-		SimpleLootTable {
-			pools: vec![
-				Pool::Common { item: Item::Saddle,    base_size: 1, add_size: None    },
-				Pool::Common { item: Item::IronIngot, base_size: 1, add_size: Some(3) },
-				Pool::Common { item: Item::Bread,     base_size: 1, add_size: None    },
-				Pool::Common { item: Item::Wheat,     base_size: 1, add_size: Some(3) },
-				Pool::Common { item: Item::Gunpowder, base_size: 1, add_size: Some(3) },
-				Pool::Common { item: Item::String,    base_size: 1, add_size: Some(3) },
-				Pool::Common { item: Item::Bucket,    base_size: 1, add_size: None    },
-				Pool::rare   ( Pool::single(Item::GoldenApple), 100),
-				Pool::rare   ( Pool::Common { item: Item::Redstone, base_size: 1, add_size: Some(3) }, 2),
-				Pool::rare   ( Pool::Table(records()), 10),
-				Pool::Common { item: Item::InkSac,    base_size: 1, add_size: None    }
+	/// Checks every entry of the table and its nested tables, so that bad tables are rejected when they are loaded.
+	pub fn validate(&self) -> Result<(), LootError> {
+		for entry in &self.entries {
+			if entry.chance == Some(0) {
+				return Err(LootError::ZeroChance);
+			}
+
+			if entry.count.min > entry.count.max {
+				return Err(LootError::InvertedCount(entry.count));
+			}
+
+			if entry.count.max > MAX_COUNT {
+				return Err(LootError::CountTooLarge(entry.count));
+			}
+
+			if let Some(ref table) = entry.table {
+				table.validate()?;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// The loot of Beta 1.7.3 dungeon chests. This is identical to the `dungeon` table in the `b173` profile.
+	pub fn beta_dungeon() -> Self {
+		LootTable {
+			rolls: 8,
+			entries: vec![
+				LootEntry::item(329, 0, 1, 1),
+				LootEntry::item(265, 0, 1, 4),
+				LootEntry::item(297, 0, 1, 1),
+				LootEntry::item(296, 0, 1, 4),
+				LootEntry::item(289, 0, 1, 4),
+				LootEntry::item(287, 0, 1, 4),
+				LootEntry::item(325, 0, 1, 1),
+				LootEntry::item(322, 0, 1, 1).with_chance(100),
+				LootEntry::item(331, 0, 1, 4).with_chance(2),
+				LootEntry::table(LootTable {
+					rolls: 1,
+					entries: vec![
+						LootEntry::item(2256, 0, 1, 1),
+						LootEntry::item(2257, 0, 1, 1)
+					]
+				}).with_chance(10),
+				LootEntry::item(351, 3, 1, 1)
 			]
 		}
 	}
 }
 
-fn records() -> SimpleLootTable {
-	SimpleLootTable {
-		pools: vec![
-			Pool::single(Item::GoldRecord),
-			Pool::single(Item::GreenRecord)
-		]
+impl Default for LootTable {
+	fn default() -> Self {
+		LootTable::beta_dungeon()
 	}
 }
 
-enum Pool {
-	/// Creates an item with a stack size of base + rng(add + 1)
-	Common { item: Item, base_size: u32, add_size: Option<u32> },
-	Decide { item: Box<Pool>, other: Option<Box<Pool>>, chance: u32 },
-	Table  (SimpleLootTable)
+/// An entry of a loot table. Entries with neither an item nor a table produce nothing, which is useful for adding
+/// weight to drawing nothing at all.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LootEntry {
+	#[serde(default="one")]
+	pub weight: u32,
+	/// 1 in N chance of the entry producing anything once it is picked, checked before the stack size is chosen.
+	#[serde(default, skip_serializing_if="Option::is_none")]
+	pub chance: Option<u32>,
+	#[serde(default, skip_serializing_if="Option::is_none")]
+	pub item: Option<ItemId>,
+	#[serde(default)]
+	pub damage: i16,
+	#[serde(default)]
+	pub count: Count,
+	#[serde(default, skip_serializing_if="Option::is_none")]
+	pub table: Option<LootTable>
 }
 
-impl Pool {
-	fn single(item: Item) -> Self {
-		Pool::Common { item, base_size: 1, add_size: None }
+impl LootEntry {
+	pub fn item(id: i16, damage: i16, min: u32, max: u32) -> Self {
+		LootEntry {
+			weight: 1,
+			chance: None,
+			item: Some(ItemId(id)),
+			damage,
+			count: Count { min, max },
+			table: None
+		}
+	}
+
+	pub fn table(table: LootTable) -> Self {
+		LootEntry {
+			weight: 1,
+			chance: None,
+			item: None,
+			damage: 0,
+			count: Count::default(),
+			table: Some(table)
+		}
 	}
-	
-	fn rare(item: Pool, chance: u32) -> Self {
-		Pool::Decide { item: Box::new(item), other: None, chance }
+
+	pub fn with_chance(mut self, chance: u32) -> Self {
+		self.chance = Some(chance);
+		self
 	}
-	
-	fn get_item(&self, rng: &mut Random) -> Option<Stack> {
-		match *self {
-			Pool::Common { ref item, base_size, add_size } => Some(Stack { 
-				item: *item, 
-				size: base_size + add_size.map(|i| rng.next_u32_bound(i + 1)).unwrap_or(0)
-			}),
-			Pool::Decide { ref item, ref other, chance   } => if rng.next_u32_bound(chance) == 0 {
-				item.get_item(rng)
-			} else {
-				other.as_ref().and_then(|o| o.get_item(rng))
-			},
-			Pool::Table  ( ref table ) => table.get_item(rng)
+
+	fn get_item(&self, rng: &mut Random) -> Option<ItemStack> {
+		if let Some(chance) = self.chance {
+			if rng.next_u32_bound(chance) != 0 {
+				return None;
+			}
+		}
+
+		if let Some(ItemId(id)) = self.item {
+			Some(ItemStack { id, damage: self.damage, count: self.count.next(rng) as i8 })
+		} else if let Some(ref table) = self.table {
+			table.get_item(rng)
+		} else {
+			None
+		}
+	}
+}
+
+/// Inclusive range of stack sizes. The size is `min + rand(max - min + 1)`, or just `min` without using the RNG
+/// if the range only contains one size.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Count {
+	pub min: u32,
+	pub max: u32
+}
+
+impl Count {
+	fn next(&self, rng: &mut Random) -> u32 {
+		if self.max > self.min {
+			self.min + rng.next_u32_bound(self.max - self.min + 1)
+		} else {
+			self.min
 		}
 	}
 }
 
+impl Default for Count {
+	fn default() -> Self {
+		Count { min: 1, max: 1 }
+	}
+}
+
+fn one() -> u32 {
+	1
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum SpawnerMob {
 	Skeleton,
 	Zombie,
@@ -113,4 +212,41 @@ impl SpawnerMob {
 			_     => unreachable!()
 		}
 	}
-}
\ No newline at end of file
+
+	/// ID of the entity spawned by the spawner, as stored in the `EntityId` tag.
+	pub fn entity_id(&self) -> &'static str {
+		match *self {
+			SpawnerMob::Skeleton => "Skeleton",
+			SpawnerMob::Zombie   => "Zombie",
+			SpawnerMob::Spider   => "Spider"
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{LootTable, LootEntry, LootError, Count};
+	use serde_json;
+	use std::collections::HashMap;
+
+	#[test]
+	fn test_beta_preset() {
+		let tables = serde_json::from_str::<HashMap<String, LootTable>>(include_str!("../../../profiles/b173/loot.json")).unwrap();
+
+		assert_eq!(tables["dungeon"], LootTable::beta_dungeon());
+		assert_eq!(tables["dungeon"].validate(), Ok(()));
+	}
+
+	#[test]
+	fn test_validate() {
+		let table = |entry: LootEntry| LootTable { rolls: 1, entries: vec![LootEntry::item(265, 0, 1, 1), entry] };
+
+		assert_eq!(table(LootEntry::item(265, 0, 1, 64)).validate(), Ok(()));
+		assert_eq!(table(LootEntry::item(265, 0, 1, 200)).validate(), Err(LootError::CountTooLarge(Count { min: 1, max: 200 })));
+		assert_eq!(table(LootEntry::item(265, 0, 4, 2)).validate(), Err(LootError::InvertedCount(Count { min: 4, max: 2 })));
+		assert_eq!(table(LootEntry::item(265, 0, 1, 1).with_chance(0)).validate(), Err(LootError::ZeroChance));
+
+		let nested = table(LootEntry::table(table(LootEntry::item(265, 0, 100, 100))));
+		assert_eq!(nested.validate(), Err(LootError::CountTooLarge(Count { min: 100, max: 100 })));
+	}
+}
@@ -1 +1,294 @@
-mod loot;
\ No newline at end of file
+use java_rand::Random;
+use vocs::indexed::Target;
+use vocs::position::QuadPosition;
+use matcher::BlockMatcher;
+use entity::tile::{TileEntity, ItemStack};
+use decorator::quad::Quad;
+use decorator::{Decorator, DecoratorFactory, Context, Result};
+use serde_json;
+
+pub mod loot;
+
+use self::loot::{LootTable, SpawnerMob};
+
+// Dungeons are at most 9x9 and centered within the quad, so they will never escape it.
+
+#[derive(Default)]
+pub struct DungeonDecoratorFactory<B>(::std::marker::PhantomData<B>);
+impl<B> DecoratorFactory<B> for DungeonDecoratorFactory<B> where B: 'static + Target + ::serde::Deserialize {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		let decorator = serde_json::from_value::<DungeonDecorator<B>>(config)?;
+
+		// Loot tables from profiles are validated when loading loot.json, but ones in decorator configs are only seen here.
+		if let Err(error) = decorator.loot.validate() {
+			return Err(::serde::de::Error::custom(format!("invalid dungeon loot table: {:?}", error)));
+		}
+
+		Ok(Box::new(decorator))
+	}
+}
+
+/// Generates a Beta dungeon: a cobblestone room with a mossy floor, a mob spawner in the center, and up to 2 chests
+/// placed against the walls.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DungeonDecorator<B> where B: Target {
+	pub blocks: DungeonBlocks<B>,
+	#[serde(default)]
+	pub settings: DungeonSettings,
+	/// Loot of the chests, by default the same as Beta 1.7.3.
+	#[serde(default)]
+	pub loot: LootTable
+}
+
+impl<B> Decorator<B> for DungeonDecorator<B> where B: Target {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
+		let center = (position.x() as i32, position.y() as i32, position.z() as i32);
+
+		let radius = (
+			(self.settings.min_radius + rng.next_u32_bound(self.settings.add_radius + 1)) as i32,
+			(self.settings.min_radius + rng.next_u32_bound(self.settings.add_radius + 1)) as i32
+		);
+
+		let room = Room { center, radius, height: self.settings.height as i32 };
+
+		if !self.blocks.check(&room, quad) {
+			return Ok(());
+		}
+
+		self.blocks.carve(&room, quad, rng, &self.settings);
+
+		for _ in 0..self.settings.chests {
+			for _ in 0..self.settings.chest_attempts {
+				let x = center.0 + rng.next_u32_bound(radius.0 as u32 * 2 + 1) as i32 - radius.0;
+				let z = center.2 + rng.next_u32_bound(radius.1 as u32 * 2 + 1) as i32 - radius.1;
+
+				let at = match room.at(x, center.1, z) {
+					Some(at) => at,
+					None => continue
+				};
+
+				if !self.blocks.empty.matches(quad.get(at)) || self.blocks.solid_neighbors(&room, quad, x, z) != 1 {
+					continue;
+				}
+
+				quad.set_immediate(at, &self.blocks.chest);
+
+				let items = self.fill_chest(rng);
+				quad.add_tile_entity(at, TileEntity::Chest { items });
+
+				break;
+			}
+		}
+
+		quad.set_immediate(position, &self.blocks.spawner);
+
+		let mob = SpawnerMob::select(rng);
+		quad.add_tile_entity(position, TileEntity::MobSpawner { entity: mob.entity_id().to_string(), delay: self.settings.spawner_delay });
+
+		Ok(())
+	}
+}
+
+impl<B> DungeonDecorator<B> where B: Target {
+	/// Draws the loot of a single chest. Each roll picks a random slot, and later rolls replace earlier items in the same slot.
+	fn fill_chest(&self, rng: &mut Random) -> Vec<(u8, ItemStack)> {
+		let mut items: Vec<(u8, ItemStack)> = Vec::new();
+
+		for _ in 0..self.loot.rolls {
+			let stack = match self.loot.get_item(rng) {
+				Some(stack) => stack,
+				None => continue
+			};
+
+			let slot = rng.next_u32_bound(self.settings.chest_slots) as u8;
+
+			items.retain(|&(existing, _)| existing != slot);
+			items.push((slot, stack));
+		}
+
+		items.sort_by_key(|&(slot, _)| slot);
+
+		items
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DungeonBlocks<B> where B: Target {
+	/// Block used for the inside of the room.
+	pub carve:   B,
+	/// Blocks that count as empty space when checking for openings and chest positions.
+	pub empty:   BlockMatcher<B>,
+	/// Blocks that the floor, ceiling, and walls of the room need to be made of.
+	pub solid:   BlockMatcher<B>,
+	pub wall:    B,
+	/// Block used for 3 out of 4 floor blocks on average, by default mossy cobblestone.
+	pub floor:   B,
+	pub chest:   B,
+	pub spawner: B
+}
+
+impl<B> DungeonBlocks<B> where B: Target {
+	fn is_solid(&self, room: &Room, quad: &mut Quad<B>, x: i32, y: i32, z: i32) -> bool {
+		room.at(x, y, z).map(|at| self.solid.matches(quad.get(at))).unwrap_or(false)
+	}
+
+	fn is_empty(&self, room: &Room, quad: &mut Quad<B>, x: i32, y: i32, z: i32) -> bool {
+		room.at(x, y, z).map(|at| self.empty.matches(quad.get(at))).unwrap_or(true)
+	}
+
+	/// Checks that the floor and ceiling are solid, and that the walls have between 1 and 5 openings that are 2 blocks tall.
+	fn check(&self, room: &Room, quad: &mut Quad<B>) -> bool {
+		let (lower, upper) = room.bounds();
+		let mut openings = 0;
+
+		for x in lower.0..(upper.0 + 1) {
+			for y in lower.1..(upper.1 + 1) {
+				for z in lower.2..(upper.2 + 1) {
+					if (y == lower.1 || y == upper.1) && !self.is_solid(room, quad, x, y, z) {
+						return false;
+					}
+
+					if room.is_side(x, z) && y == room.center.1 && self.is_empty(room, quad, x, y, z) && self.is_empty(room, quad, x, y + 1, z) {
+						openings += 1;
+					}
+				}
+			}
+		}
+
+		openings >= 1 && openings <= 5
+	}
+
+	/// Hollows out the room and builds the walls and floor. Walls are only placed where there is already a solid block
+	/// that is supported from below, so openings and overhangs are preserved.
+	fn carve(&self, room: &Room, quad: &mut Quad<B>, rng: &mut Random, settings: &DungeonSettings) {
+		let (lower, upper) = room.bounds();
+
+		for x in lower.0..(upper.0 + 1) {
+			// The ceiling is left untouched, so Y starts just below it.
+			for y in (lower.1..upper.1).rev() {
+				for z in lower.2..(upper.2 + 1) {
+					let at = match room.at(x, y, z) {
+						Some(at) => at,
+						None => continue
+					};
+
+					if !room.is_side(x, z) && y != lower.1 {
+						quad.set_immediate(at, &self.carve);
+					} else if y >= 0 && !self.is_solid(room, quad, x, y - 1, z) {
+						quad.set_immediate(at, &self.carve);
+					} else if self.solid.matches(quad.get(at)) {
+						if y == lower.1 && rng.next_u32_bound(settings.moss_chance) != 0 {
+							quad.set_immediate(at, &self.floor);
+						} else {
+							quad.set_immediate(at, &self.wall);
+						}
+					}
+				}
+			}
+		}
+	}
+
+	fn solid_neighbors(&self, room: &Room, quad: &mut Quad<B>, x: i32, z: i32) -> u32 {
+		let y = room.center.1;
+
+		[(-1, 0), (1, 0), (0, -1), (0, 1)].iter()
+			.filter(|&&(x_offset, z_offset)| self.is_solid(room, quad, x + x_offset, y, z + z_offset))
+			.count() as u32
+	}
+}
+
+/// The interior of a dungeon, along with the 1 block thick shell of walls around it.
+struct Room {
+	center: (i32, i32, i32),
+	/// Radius of the interior on the X and Z axis.
+	radius: (i32, i32),
+	height: i32
+}
+
+impl Room {
+	/// Inclusive bounds of the room including the walls, floor, and ceiling.
+	fn bounds(&self) -> ((i32, i32, i32), (i32, i32, i32)) {
+		(
+			(self.center.0 - self.radius.0 - 1, self.center.1 - 1,               self.center.2 - self.radius.1 - 1),
+			(self.center.0 + self.radius.0 + 1, self.center.1 + self.height + 1, self.center.2 + self.radius.1 + 1)
+		)
+	}
+
+	fn is_side(&self, x: i32, z: i32) -> bool {
+		let (lower, upper) = self.bounds();
+
+		x == lower.0 || x == upper.0 || z == lower.2 || z == upper.2
+	}
+
+	/// Converts a position to a quad position, returning None if it is outside of the quad.
+	fn at(&self, x: i32, y: i32, z: i32) -> Option<QuadPosition> {
+		if x < 0 || x >= 32 || y < 0 || y >= 256 || z < 0 || z >= 32 {
+			None
+		} else {
+			Some(QuadPosition::new(x as u8, y as u8, z as u8))
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DungeonSettings {
+	/// Height of the interior of the room.
+	pub height: u32,
+	pub min_radius: u32,
+	pub add_radius: u32,
+	/// Number of chests to try to place.
+	pub chests: u32,
+	/// Number of positions to try for each chest before giving up on it.
+	pub chest_attempts: u32,
+	pub chest_slots: u32,
+	/// 1 in N chance of a floor block being regular cobblestone instead of mossy cobblestone.
+	pub moss_chance: u32,
+	/// Ticks until the spawner first tries to spawn mobs.
+	pub spawner_delay: i16
+}
+
+impl Default for DungeonSettings {
+	fn default() -> Self {
+		DungeonSettings {
+			height:         3,
+			min_radius:     2,
+			add_radius:     1,
+			chests:         2,
+			chest_attempts: 3,
+			chest_slots:    27,
+			moss_chance:    4,
+			spawner_delay:  20
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::DungeonDecoratorFactory;
+	use decorator::DecoratorFactory;
+	use serde_json;
+
+	fn config(loot: &str) -> serde_json::Value {
+		serde_json::from_str(&format!(r#"{{
+			"blocks": {{
+				"carve": 0,
+				"empty": {{ "blacklist": false, "blocks": [0] }},
+				"solid": {{ "blacklist": true, "blocks": [0] }},
+				"wall": 64,
+				"floor": 768,
+				"chest": 864,
+				"spawner": 832
+			}},
+			"loot": {}
+		}}"#, loot)).unwrap()
+	}
+
+	#[test]
+	fn test_configure_validates_loot() {
+		let factory = DungeonDecoratorFactory::<u16>::default();
+
+		assert!(factory.configure(config(r#"{ "entries": [{ "item": 265, "count": { "min": 1, "max": 64 } }] }"#)).is_ok());
+		assert!(factory.configure(config(r#"{ "entries": [{ "item": 265, "count": { "min": 1, "max": 65 } }] }"#)).is_err());
+		assert!(factory.configure(config(r#"{ "entries": [{ "item": 265, "chance": 0 }] }"#)).is_err());
+	}
+}
@@ -1,10 +1,20 @@
 use java_rand::Random;
 use vocs::indexed::Target;
-use vocs::view::QuadMut;
+use decorator::quad::Quad;
 use vocs::position::{QuadPosition, Offset, dir};
-use decorator::{Decorator, Result};
+use decorator::{Decorator, DecoratorFactory, Context, Result};
 use matcher::BlockMatcher;
+use serde_json;
 
+#[derive(Default)]
+pub struct ExposedDecoratorFactory<B>(::std::marker::PhantomData<B>);
+impl<B> DecoratorFactory<B> for ExposedDecoratorFactory<B> where B: 'static + Target + ::serde::Deserialize {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		Ok(Box::new(serde_json::from_value::<ExposedDecorator<B>>(config)?))
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ExposedDecorator<B> where B: Target {
 	pub block: B,
 	pub stone: BlockMatcher<B>,
@@ -12,7 +22,7 @@ pub struct ExposedDecorator<B> where B: Target {
 }
 
 impl<B> Decorator<B> for ExposedDecorator<B> where B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, _: &mut Random, position: QuadPosition) -> Result {
+	fn generate(&self, quad: &mut Quad<B>, _: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
 		if !self.stone.matches(quad.get(position)) {
 			return Ok(());
 		}
@@ -0,0 +1,223 @@
+//! Height distributions for dispatchers, including ones that place decorators on the surface of the terrain.
+
+use java_rand::Random;
+use vocs::indexed::Target;
+use vocs::view::QuadMut;
+use vocs::position::{ColumnPosition, QuadPosition};
+use decorator::quad::Quad;
+use distribution::{Distribution, Chance, Baseline};
+use block::BlockRegistry;
+use matcher::BlockMatcher;
+
+/// Picks the position of each attempt of a dispatcher, relative to the centered column.
+/// Plain distributions pick the X, Y, and Z coordinates in that order, like most Notchian decorators. Distributions
+/// that depend on the terrain need to know the column first, so they pick the X and Z coordinates before the Y.
+/// Returns None if there is no valid position in the column, in which case the attempt is skipped.
+pub trait HeightDistribution<B> where B: Target {
+	fn position(&self, quad: &Quad<B>, rng: &mut Random) -> Option<ColumnPosition>;
+}
+
+impl<D, B> HeightDistribution<B> for D where D: Distribution, B: Target {
+	fn position(&self, _: &Quad<B>, rng: &mut Random) -> Option<ColumnPosition> {
+		Some(ColumnPosition::new(
+			rng.next_u32_bound(16) as u8,
+			self.next(rng) as u8,
+			rng.next_u32_bound(16) as u8
+		))
+	}
+}
+
+/// Which blocks count towards the height of a column.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum HeightmapKind {
+	/// Blocks that are not fully transparent to light, like `getHeightValue`.
+	LightBlocking,
+	/// Blocks that are solid, and liquids.
+	MotionBlocking,
+	/// Solid blocks that are fully opaque, which excludes blocks such as leaves and glass.
+	TopSolid,
+	/// Solid blocks, which excludes liquids so that the height is at the bottom of oceans and lakes.
+	OceanFloor
+}
+
+impl HeightmapKind {
+	pub fn matcher<B>(&self, blocks: &BlockRegistry<B>) -> BlockMatcher<B> where B: Target {
+		match *self {
+			HeightmapKind::LightBlocking  => blocks.matcher(|properties| properties.opacity > 0),
+			HeightmapKind::MotionBlocking => blocks.matcher(|properties| properties.solid || properties.liquid.is_some()),
+			HeightmapKind::TopSolid       => blocks.matcher(|properties| properties.solid && properties.opacity >= 15),
+			HeightmapKind::OceanFloor     => blocks.matcher(|properties| properties.solid)
+		}
+	}
+}
+
+/// Places attempts on top of the highest block of the column that counts towards the height, plus the offset.
+/// Attempts that would end up outside of the world are skipped.
+#[derive(Debug, Clone)]
+pub struct Heightmap<B> where B: Target {
+	/// Blocks that count towards the height of a column.
+	pub blocks: BlockMatcher<B>,
+	pub offset: i32
+}
+
+impl<B> Heightmap<B> where B: Target {
+	pub fn new(kind: HeightmapKind, blocks: &BlockRegistry<B>) -> Self {
+		Heightmap {
+			blocks: kind.matcher(blocks),
+			offset: 0
+		}
+	}
+
+	/// Returns the Y coordinate above the highest block of the column that counts, or 0 if there is none.
+	/// Returns None if the highest block that counts is at the top of the world, leaving no room above it.
+	/// The X and Z coordinates are within the quad.
+	pub fn height(&self, quad: &QuadMut<B>, x: u8, z: u8) -> Option<u8> {
+		match (0..256u32).rev().find(|&y| self.blocks.matches(quad.get(QuadPosition::new(x, y as u8, z)))) {
+			Some(255) => None,
+			Some(y) => Some(y as u8 + 1),
+			None => Some(0)
+		}
+	}
+}
+
+impl<B> HeightDistribution<B> for Heightmap<B> where B: Target {
+	fn position(&self, quad: &Quad<B>, rng: &mut Random) -> Option<ColumnPosition> {
+		let x = rng.next_u32_bound(16) as u8;
+		let z = rng.next_u32_bound(16) as u8;
+
+		let y = match self.height(quad, x + 8, z + 8) {
+			Some(height) => height as i32 + self.offset,
+			None => return None
+		};
+
+		if y < 0 || y > 255 {
+			return None;
+		}
+
+		Some(ColumnPosition::new(x, y as u8, z))
+	}
+}
+
+/// Either a plain distribution, or a heightmap. Used by configured dispatchers.
+#[derive(Debug, Clone)]
+pub enum Height<B> where B: Target {
+	Distribution(Chance<Baseline>),
+	Heightmap(Heightmap<B>)
+}
+
+impl<B> HeightDistribution<B> for Height<B> where B: Target {
+	fn position(&self, quad: &Quad<B>, rng: &mut Random) -> Option<ColumnPosition> {
+		match *self {
+			Height::Distribution(ref distribution) => distribution.position(quad, rng),
+			Height::Heightmap(ref heightmap) => heightmap.position(quad, rng)
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Heightmap, HeightmapKind, HeightDistribution};
+	use java_rand::Random;
+	use vocs::position::{GlobalColumnPosition, QuadPosition};
+	use decorator::quad::Quad;
+	use block::{BlockRegistry, BlockProperties, Liquid};
+
+	const SAND: u16 = 12*16;
+	const WATER: u16 = 9*16;
+	const LEAVES: u16 = 18*16;
+	const GLASS: u16 = 20*16;
+
+	fn registry() -> BlockRegistry<u16> {
+		let mut registry = BlockRegistry::new(BlockProperties::default());
+
+		registry.set(0, BlockProperties { opacity: 0, solid: false, replaceable: true, .. BlockProperties::default() });
+		registry.set(WATER, BlockProperties { opacity: 3, solid: false, liquid: Some(Liquid::Water), replaceable: true, .. BlockProperties::default() });
+		registry.set(LEAVES, BlockProperties { opacity: 1, .. BlockProperties::default() });
+		registry.set(GLASS, BlockProperties { opacity: 0, .. BlockProperties::default() });
+
+		registry
+	}
+
+	#[test]
+	fn test_heightmap_kinds() {
+		let blocks = registry();
+		let mut world = ::test::quad_world(SAND, 60);
+		let mut quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		// Water over sand.
+		for y in 60..64 {
+			quad.set_immediate(QuadPosition::new(8, y, 8), &WATER);
+		}
+
+		// Leaves and then glass over sand.
+		for y in 60..64 {
+			quad.set_immediate(QuadPosition::new(9, y, 8), &SAND);
+		}
+
+		quad.set_immediate(QuadPosition::new(9, 64, 8), &LEAVES);
+		quad.set_immediate(QuadPosition::new(9, 65, 8), &GLASS);
+
+		// Nothing at all.
+		for y in 0..60 {
+			quad.set_immediate(QuadPosition::new(10, y, 8), &0);
+		}
+
+		// A block at the top of the world.
+		quad.set_immediate(QuadPosition::new(11, 255, 8), &SAND);
+
+		let expected = [
+			(HeightmapKind::LightBlocking,  Some(64), Some(65)),
+			(HeightmapKind::MotionBlocking, Some(64), Some(66)),
+			(HeightmapKind::TopSolid,       Some(60), Some(64)),
+			(HeightmapKind::OceanFloor,     Some(60), Some(66))
+		];
+
+		for &(kind, water, glass) in &expected {
+			let heightmap = Heightmap::new(kind, &blocks);
+
+			assert_eq!(heightmap.height(&quad, 8, 8), water, "{:?} over water", kind);
+			assert_eq!(heightmap.height(&quad, 9, 8), glass, "{:?} over glass", kind);
+			assert_eq!(heightmap.height(&quad, 10, 8), Some(0), "{:?} over nothing", kind);
+			assert_eq!(heightmap.height(&quad, 11, 8), None, "{:?} at the top of the world", kind);
+		}
+	}
+
+	#[test]
+	fn test_heightmap_position() {
+		let blocks = registry();
+		let mut world = ::test::quad_world(SAND, 60);
+		let mut quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		let heightmap = Heightmap { offset: -1, .. Heightmap::new(HeightmapKind::OceanFloor, &blocks) };
+
+		for seed in 0..16 {
+			let mut rng = Random::new(seed);
+			let mut expected = Random::new(seed);
+
+			let position = heightmap.position(&quad, &mut rng).unwrap();
+
+			// The offset replaces the top block instead of placing on top of it.
+			assert_eq!(position.x() as u32, expected.next_u32_bound(16));
+			assert_eq!(position.z() as u32, expected.next_u32_bound(16));
+			assert_eq!(position.y(), 59);
+		}
+
+		// Once the top block of every column is at the top of the world, each attempt is skipped. The X and Z
+		// coordinates are still picked, keeping the RNG in sync.
+		for x in 0..32 {
+			for z in 0..32 {
+				quad.set_immediate(QuadPosition::new(x, 255, z), &SAND);
+			}
+		}
+
+		let heightmap = Heightmap::new(HeightmapKind::OceanFloor, &blocks);
+
+		let mut rng = Random::new(0);
+		let mut expected = Random::new(0);
+		expected.next_u32_bound(16);
+		expected.next_u32_bound(16);
+
+		assert!(heightmap.position(&quad, &mut rng).is_none());
+		assert_eq!(rng.next_u64(), expected.next_u64());
+	}
+}
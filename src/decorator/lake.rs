@@ -3,19 +3,31 @@ use vocs::indexed::Target;
 use matcher::BlockMatcher;
 use vocs::position::{ChunkPosition, ColumnPosition, QuadPosition};
 use vocs::view::QuadMut;
+use decorator::quad::Quad;
 use vocs::mask::ChunkMask;
 use vocs::component::*;
-use super::{Decorator, Result};
+use super::{Decorator, DecoratorFactory, Context, Result};
+use serde_json;
 
 // Since lakes are always 16x8x16, they will never escape the Quad.
 
+#[derive(Default)]
+pub struct LakeDecoratorFactory<B>(::std::marker::PhantomData<B>);
+impl<B> DecoratorFactory<B> for LakeDecoratorFactory<B> where B: 'static + Target + ::serde::Deserialize {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		Ok(Box::new(serde_json::from_value::<LakeDecorator<B>>(config)?))
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LakeDecorator<B> where B: Target {
 	pub blocks: LakeBlocks<B>,
+	#[serde(default)]
 	pub settings: LakeSettings
 }
 
 impl<B> Decorator<B> for LakeDecorator<B> where B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, rng: &mut Random, position: QuadPosition) -> Result {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
 		let mut lower = position.to_centered().unwrap();
 
 		while lower.y() > 0 && quad.get(QuadPosition::new(lower.x(), lower.y(), lower.z())) == &self.blocks.carve {
@@ -44,9 +56,11 @@ impl<B> Decorator<B> for LakeDecorator<B> where B: Target {
 	}
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LakeBlocks<B> where B: Target {
 	pub is_liquid:  BlockMatcher<B>,
 	pub is_solid:   BlockMatcher<B>,
+	#[serde(rename="replaceable")]
 	pub replacable: BlockMatcher<B>,
 	pub liquid:     B,
 	pub carve:      B,
@@ -113,6 +127,7 @@ impl<B> LakeBlocks<B> where B: Target {
 	// TODO: grow_grass, solidify_border
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LakeSettings {
 	pub surface: u8,
 	pub min_blobs: u32,
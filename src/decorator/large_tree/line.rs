@@ -1,104 +1,124 @@
-use vocs::position::{QuadPosition, Dir, Axis};
-use vocs::view::{QuadBlocks, QuadAssociation};
-use std::cmp;
-
-// TODO: This should be close enough, but is unverified.
-
+/// A line between 2 blocks, traced like the Notchian large tree generator: one block is visited for each step along
+/// the axis with the largest difference, and the other 2 coordinates are interpolated.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Line {
-	pub from: QuadPosition,
-	pub to:   QuadPosition
+	pub from: (i32, i32, i32),
+	pub to:   (i32, i32, i32)
 }
 
 impl Line {
+	pub fn new(from: (i32, i32, i32), to: (i32, i32, i32)) -> Self {
+		Line { from, to }
+	}
+
 	/// Offset that needs to be applied to `from` to get `to`.
-	pub fn offset(&self) -> (i8, i8, i8) {
+	pub fn offset(&self) -> (i32, i32, i32) {
 		(
-			(self.to.x() as i8) - (self.from.x() as i8),
-			(self.to.y() as i8) - (self.from.y() as i8),
-			(self.to.z() as i8) - (self.from.z() as i8)
+			self.to.0 - self.from.0,
+			self.to.1 - self.from.1,
+			self.to.2 - self.from.2
 		)
 	}
 
-	pub fn trace(&self) -> LineTracer {
-		let diff = self.offset();
+	/// Traces the blocks that are placed when drawing the line, including both ends. The interpolated coordinates are rounded.
+	pub fn draw(&self) -> LineTracer {
+		LineTracer::new(self, 0.5)
+	}
 
-		let max = cmp::max (
-			diff.0.abs(),
-			cmp::max (
-				diff.1.abs(),
-				diff.2.abs()
-			)
-		);
+	/// Traces the blocks that are checked when testing whether the line is obstructed, including both ends.
+	/// Unlike `draw`, the interpolated coordinates are floored instead of rounded.
+	pub fn check(&self) -> LineTracer {
+		LineTracer::new(self, 0.0)
+	}
+}
 
-		let equal = (diff.0 == max, diff.1 == max, diff.2 == max);
-		let mask = (equal.0 as u8) | ((equal.1 as u8) << 1) | ((equal.2 as u8) << 2);
+pub struct LineTracer {
+	from: [i32; 3],
+	/// Index of the axis with the largest difference.
+	primary: usize,
+	/// Change in the 2 other coordinates for each step along the primary axis.
+	slopes: [f64; 3],
+	/// Either 1 or -1.
+	step: i32,
+	/// Offset along the primary axis of the next block, and the offset that ends the line.
+	current: i32,
+	end: i32,
+	rounding: f64
+}
 
-		let axis = match mask.trailing_zeros() & 3 {
-			0 => Axis::X,
-			1 => Axis::Y,
-			2 => Axis::Z,
-			_ => unreachable!()
-		};
+impl LineTracer {
+	fn new(line: &Line, rounding: f64) -> Self {
+		let offset = line.offset();
+		let offset = [offset.0, offset.1, offset.2];
 
-		LineTracer {
-			steps: max as u32,
-			iterations: 0,
-			velocity: (
-				(diff.0 as f64) / (max as f64),
-				(diff.1 as f64) / (max as f64),
-				(diff.2 as f64) / (max as f64)
-			),
-			position: (
-				self.from.x() as f64,
-				self.from.y() as f64,
-				self.from.z() as f64
-			),
-			direction: if max > 0 { axis.plus() } else { axis.minus() }
+		let mut primary = 0;
+
+		for axis in 1..3 {
+			if offset[axis].abs() > offset[primary].abs() {
+				primary = axis;
+			}
 		}
-	}
 
-	pub fn draw(&self, blocks: &mut QuadBlocks, association: &mut QuadAssociation) {
-		for position in self.trace() {
-			blocks.set(position, association);
+		let step = if offset[primary] > 0 { 1 } else { -1 };
+
+		let mut slopes = [0.0; 3];
+
+		if offset[primary] != 0 {
+			for axis in 0..3 {
+				slopes[axis] = (offset[axis] as f64) / (offset[primary] as f64);
+			}
 		}
-	}
-}
 
-pub struct LineTracer {
-	velocity: (f64, f64, f64),
-	position: (f64, f64, f64),
-	steps: u32,
-	iterations: u32,
-	direction: Dir
+		LineTracer {
+			from: [line.from.0, line.from.1, line.from.2],
+			primary,
+			slopes,
+			step,
+			current: 0,
+			// Lines of length 0 do not visit any blocks.
+			end: if offset[primary] != 0 { offset[primary] + step } else { 0 },
+			rounding
+		}
+	}
 }
 
 impl Iterator for LineTracer {
-	type Item = QuadPosition;
+	type Item = (i32, i32, i32);
 
 	fn next(&mut self) -> Option<Self::Item> {
-		if self.iterations >= self.steps {
+		if self.current == self.end {
 			return None;
 		}
 
-		let mut position = [
-			self.position.0 + self.velocity.0,
-			self.position.1 + self.velocity.1,
-			self.position.2 + self.velocity.2
-		];
+		let mut position = [0; 3];
 
-		let primary_offset = (self.iterations as i32) * ( if self.direction.plus() { 1 } else { -1 });
+		for axis in 0..3 {
+			position[axis] = if axis == self.primary {
+				self.from[axis] + self.current
+			} else {
+				((self.from[axis] as f64) + (self.current as f64) * self.slopes[axis] + self.rounding).floor() as i32
+			};
+		}
+
+		self.current += self.step;
+
+		Some((position[0], position[1], position[2]))
+	}
+}
 
-		position[self.direction.axis() as usize] = self.position.0 + (primary_offset as f64);
+#[cfg(test)]
+mod test {
+	use super::Line;
 
-		let position = QuadPosition::new(
-			(position[0] + 0.5).floor() as u8,
-			(position[1] + 0.5).floor() as u8,
-			(position[2] + 0.5).floor() as u8
-		);
+	#[test]
+	fn test_trace() {
+		assert_eq!(Line::new((0, 0, 0), (0, 0, 0)).draw().count(), 0);
+		assert_eq!(Line::new((4, 10, 4), (4, 13, 4)).draw().collect::<Vec<_>>(), vec![(4, 10, 4), (4, 11, 4), (4, 12, 4), (4, 13, 4)]);
 
-		self.iterations += 1;
+		// X is the primary axis, Z is interpolated with a slope of -0.5.
+		let line = Line::new((0, 0, 0), (-4, 0, 2));
 
-		Some(position)
+		assert_eq!(line.draw().collect::<Vec<_>>(),  vec![(0, 0, 0), (-1, 0, 1), (-2, 0, 1), (-3, 0, 2), (-4, 0, 2)]);
+		assert_eq!(line.check().collect::<Vec<_>>(), vec![(0, 0, 0), (-1, 0, 0), (-2, 0, 1), (-3, 0, 1), (-4, 0, 2)]);
 	}
-}
\ No newline at end of file
+}
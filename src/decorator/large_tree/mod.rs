@@ -1,40 +1,239 @@
 use java_rand::Random;
+use vocs::indexed::Target;
+use vocs::position::QuadPosition;
+use matcher::BlockMatcher;
+use decorator::quad::Quad;
+use decorator::{Decorator, DecoratorFactory, Context, Result};
+use serde_json;
 use std::cmp::min;
 
 mod line;
 
+use self::line::Line;
+
 const TAU: f64 = 2.0 * 3.14159;
 
+#[derive(Default)]
+pub struct LargeTreeDecoratorFactory<B>(::std::marker::PhantomData<B>);
+impl<B> DecoratorFactory<B> for LargeTreeDecoratorFactory<B> where B: 'static + Target + ::serde::Deserialize {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		Ok(Box::new(serde_json::from_value::<LargeTreeDecorator<B>>(config)?))
+	}
+}
+
+/// Generates the large oak trees of Beta, with a tall trunk, branches, and multiple foliage clusters.
+/// Parts of the tree outside of the quad are spilled into the neighboring columns.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LargeTreeDecorator<B> where B: Target {
+	pub blocks: LargeTreeBlocks<B>,
+	#[serde(default)]
+	pub settings: LargeTreeSettings
+}
+
+impl<B> Decorator<B> for LargeTreeDecorator<B> where B: Target {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
+		// The Notchian generator uses a separate RNG for the shape of the tree.
+		let mut rng = Random::new(rng.next_u64());
+
+		let origin = (position.x() as i32, position.y() as i32, position.z() as i32);
+		let mut height = self.settings.min_height + rng.next_i32_bound(self.settings.add_height + 1);
+
+		match self.blocks.get(quad, (origin.0, origin.1 - 1, origin.2)) {
+			Some(below) if self.blocks.soil.matches(below) => (),
+			_ => return Ok(())
+		}
+
+		if let Some(clear) = self.blocks.obstruction(quad, Line::new(origin, (origin.0, origin.1 + height - 1, origin.2))) {
+			if clear < self.settings.min_clear_height {
+				return Ok(());
+			}
+
+			height = clear;
+		}
+
+		let tree = self.settings.tree(origin, height);
+		let clusters = self.clusters(quad, &mut rng, &tree);
+
+		for cluster in &clusters {
+			for y_offset in 0..tree.foliage_height {
+				let radius = tree.foliage_radius(y_offset);
+
+				self.blocks.foliage_layer(quad, (cluster.position.0, cluster.position.1 + y_offset, cluster.position.2), radius);
+			}
+		}
+
+		self.blocks.draw(quad, Line::new(origin, (origin.0, tree.trunk_top, origin.2)), &self.blocks.log);
+
+		for cluster in &clusters {
+			if tree.needs_branch(cluster.branch_y) {
+				self.blocks.draw(quad, Line::new((origin.0, cluster.branch_y, origin.2), cluster.position), &self.blocks.log);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl<B> LargeTreeDecorator<B> where B: Target {
+	/// Picks the foliage clusters of the tree, dropping every cluster that would be obstructed or that could not be
+	/// connected to the trunk. The first cluster is always the one at the top of the trunk.
+	fn clusters(&self, quad: &mut Quad<B>, rng: &mut Random, tree: &LargeTree) -> Vec<Foliage> {
+		let mut clusters = Vec::with_capacity((tree.foliage_per_y * tree.height) as usize);
+
+		clusters.push(Foliage {
+			position: (tree.origin.0, tree.foliage_max_y, tree.origin.2),
+			branch_y: tree.trunk_top
+		});
+
+		let mut y = tree.foliage_max_y - 1;
+		let mut layer = tree.foliage_max_y - tree.origin.1;
+
+		while layer >= 0 {
+			if let Some(spread) = tree.spread(layer) {
+				for _ in 0..tree.foliage_per_y {
+					let foliage = tree.foliage(y, spread, rng);
+					let top = (foliage.position.0, foliage.position.1 + tree.foliage_height, foliage.position.2);
+
+					if self.blocks.obstruction(quad, Line::new(foliage.position, top)).is_some() {
+						continue;
+					}
+
+					if self.blocks.obstruction(quad, Line::new((tree.origin.0, foliage.branch_y, tree.origin.2), foliage.position)).is_some() {
+						continue;
+					}
+
+					clusters.push(foliage);
+				}
+			}
+
+			y -= 1;
+			layer -= 1;
+		}
+
+		clusters
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LargeTreeBlocks<B> where B: Target {
+	pub log:     B,
+	pub foliage: B,
+	/// Blocks that do not obstruct the tree, and that foliage may replace.
+	pub replace: BlockMatcher<B>,
+	/// Blocks that the tree can grow on.
+	pub soil:    BlockMatcher<B>
+}
+
+impl<B> LargeTreeBlocks<B> where B: Target {
+	fn get<'a>(&self, quad: &'a Quad<B>, position: (i32, i32, i32)) -> Option<&'a B> {
+		to_quad(position).map(|position| quad.get(position))
+	}
+
+
+	/// Returns the distance along the line to the first block that is not replaceable, or None if the whole line is clear.
+	/// Blocks outside of the quad never obstruct the line.
+	fn obstruction(&self, quad: &Quad<B>, line: Line) -> Option<i32> {
+		for (distance, position) in line.check().enumerate() {
+			if let Some(block) = self.get(quad, position) {
+				if !self.replace.matches(block) {
+					return Some(distance as i32);
+				}
+			}
+		}
+
+		None
+	}
+
+	fn draw(&self, quad: &mut Quad<B>, line: Line, block: &B) {
+		let all = BlockMatcher::all();
+
+		for position in line.draw() {
+			quad.set_or_spill(position, block, &all);
+		}
+	}
+
+	/// Places a horizontal disc of foliage. Blocks are included if the distance from the center to the far corner
+	/// of the block is within the radius.
+	fn foliage_layer(&self, quad: &mut Quad<B>, center: (i32, i32, i32), radius: f32) {
+		let extent = (radius as f64 + 0.618) as i32;
+		let radius_sq = (radius * radius) as f64;
+
+		for x_offset in -extent..(extent + 1) {
+			for z_offset in -extent..(extent + 1) {
+				let distance_sq = ((x_offset.abs() as f64) + 0.5).powi(2) + ((z_offset.abs() as f64) + 0.5).powi(2);
+
+				if distance_sq > radius_sq {
+					continue;
+				}
+
+				let position = (center.0 + x_offset, center.1, center.2 + z_offset);
+
+				quad.set_or_spill(position, &self.foliage, &self.replace);
+			}
+		}
+	}
+}
+
+impl Default for LargeTreeBlocks<u16> {
+	fn default() -> Self {
+		LargeTreeBlocks {
+			log:     17*16,
+			foliage: 18*16,
+			replace: BlockMatcher::include([0*16, 18*16].iter()),
+			soil:    BlockMatcher::include([2*16, 3*16].iter())
+		}
+	}
+}
+
+impl Default for LargeTreeDecorator<u16> {
+	fn default() -> Self {
+		LargeTreeDecorator {
+			blocks: LargeTreeBlocks::default(),
+			settings: LargeTreeSettings::default()
+		}
+	}
+}
+
+fn to_quad(position: (i32, i32, i32)) -> Option<QuadPosition> {
+	if position.0 < 0 || position.0 >= 32 || position.1 < 0 || position.1 >= 256 || position.2 < 0 || position.2 >= 32 {
+		None
+	} else {
+		Some(QuadPosition::new(position.0 as u8, position.1 as u8, position.2 as u8))
+	}
+}
+
 /// A foliage cluster. "Balloon" oaks in Minecraft are simply a large tree generating a single foliage cluster at the top of the very short trunk.
 #[derive(Debug)]
-pub struct Foilage {
+pub struct Foliage {
 	/// Location of the leaf cluster, and the endpoint of the branch line. The Y is at the bottom of the cluster.
-	cluster: (i32, i32, i32),
-	/// Y coordinate of the block above the top of this foliage cluster.
-	foliage_top_y: i32,
-	/// Y coordinate of the start of the branch line. The X and Z coordinate are always equal to the orgin of the tree.
-	branch_y: i32
+	pub position: (i32, i32, i32),
+	/// Y coordinate of the start of the branch line. The X and Z coordinate are always equal to the origin of the tree.
+	pub branch_y: i32
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LargeTreeSettings {
 	/// Makes the branches shorter or longer than the default.
-	branch_scale: f64,
+	pub branch_scale: f64,
 	/// For every 1 block the branch is long, this multiplier determines how many blocks it will go down on the trunk.
-	branch_slope: f64,
-	/// Default height of the leaves of the foliage clusters, from top to bottom.
-	/// When added to the Y of the cluster, represents the coordinate of the top layer of the leaf cluster.
-	foliage_height: i32,
+	pub branch_slope: f64,
+	/// Branches starting lower than this fraction of the height of the tree are not placed, leaving the cluster floating.
+	pub min_branch_height: f64,
+	/// Height of the foliage clusters, from top to bottom. The Notchian generator uses 4 by default, but population
+	/// always sets it to 5.
+	pub foliage_height: i32,
 	/// Factor in determining the amount of foliage clusters generated on each Y level of the big tree.
-	foliage_density: f64,
+	pub foliage_density: f64,
 	/// Added to the foliage_per_y value before conversion to i32.
-	base_foliage_per_y: f64,
+	pub base_foliage_per_y: f64,
 	/// How tall the trunk is in comparison to the total height. Should be 0.0 to 1.0.
-	trunk_height_scale: f64,
+	pub trunk_height_scale: f64,
 	/// Minimum height of the tree.
-	min_height: i32,
+	pub min_height: i32,
 	/// Maximum height that can be added to the minimum. Max height of the tree = min_height + add_height.
-	add_height: i32
+	pub add_height: i32,
+	/// If the space above the tree is obstructed, the tree is shortened to fit as long as it is at least this tall.
+	pub min_clear_height: i32
 }
 
 impl Default for LargeTreeSettings {
@@ -42,43 +241,35 @@ impl Default for LargeTreeSettings {
 		LargeTreeSettings {
 			branch_scale: 1.0,
 			branch_slope: 0.381,
-			foliage_height: 4,
+			min_branch_height: 0.2,
+			foliage_height: 5,
 			foliage_density: 1.0,
 			base_foliage_per_y: 1.382,
 			trunk_height_scale: 0.618,
 			min_height: 5,
-			add_height: 11
+			add_height: 11,
+			min_clear_height: 6
 		}
 	}
 }
 
 impl LargeTreeSettings {
-	pub fn tree(&self, orgin: (i32, i32, i32), rng: &mut Random, preset_height: Option<i32>, max_height: i32) -> LargeTree {
-		let height = min(preset_height.unwrap_or_else(|| self.min_height + rng.next_i32_bound(self.add_height + 1)), max_height);
-		let height_f32 = height as f32;
+	pub fn tree(&self, origin: (i32, i32, i32), height: i32) -> LargeTree {
 		let height_f64 = height as f64;
-		
-		let spread_center    = height_f32 / 2.0;
-		let spread_center_sq = spread_center.powi(2);
-		
+
 		let trunk_height = min((height_f64 * self.trunk_height_scale) as i32, height - 1);
-		let trunk_top    = orgin.1 + trunk_height;
-		
+
 		let foliage_per_y = ((self.foliage_density * height_f64 / 13.0).powi(2) + self.base_foliage_per_y).max(1.0) as i32;
-		let foliage_max_y = orgin.1 + height - self.foliage_height;
-		let foliage_min_y = orgin.1 + (height_f32 * 0.3).ceil() as i32 - 1;
-		
-		LargeTree { 
-			orgin, 
-			spread_center, 
-			spread_center_sq, 
-			height, 
-			trunk_top, 
+
+		LargeTree {
+			origin,
+			height,
+			trunk_top: origin.1 + trunk_height,
 			foliage_per_y,
-			foliage_max_y,
-			foliage_min_y,
+			foliage_max_y: origin.1 + height - self.foliage_height,
 			branch_scale: self.branch_scale,
 			branch_slope: self.branch_slope,
+			min_branch_height: self.min_branch_height,
 			foliage_height: self.foliage_height
 		}
 	}
@@ -87,68 +278,249 @@ impl LargeTreeSettings {
 #[derive(Debug)]
 pub struct LargeTree {
 	/// Coordinate pointing to the bottom log of the trunk.
-	pub orgin:            (i32, i32, i32),
-	/// The place where most of the foliage should be centered around. At this point, the foliage should spread out the most.
-	/// If outside the range of the foliage range, then it will instead spread out the foliage the most at the bottom/top.
-	pub spread_center:    f32,
-	/// A squared version of the spread_center.
-	pub spread_center_sq: f32,
+	pub origin:            (i32, i32, i32),
 	/// Maximum length from bottom to top of the tree.
-	pub height:           i32,
+	pub height:            i32,
 	/// Y coordinate of the uppermost block of the trunk.
-	pub trunk_top:        i32,
+	pub trunk_top:         i32,
 	/// Foilage per Y layer. At least 1.
-	pub foliage_per_y:    i32,
-	/// Maximum Y value for foliage layers to spawn (Inclusive).
-	/// A single foliage cluster is found at this value, centered on the trunk.
-	/// For the purposes of iterating through the generated foliage layers, this can be considered Exclusive.
-	pub foliage_max_y:    i32,
-	/// Minimum Y value for foliage layers to spawn (Inclusive)
-	pub foliage_min_y:    i32,
+	pub foliage_per_y:     i32,
+	/// Y coordinate of the single foliage cluster centered on the trunk. The other clusters are below it.
+	pub foliage_max_y:     i32,
 	/// Makes the branches shorter or longer than the default.
-	pub branch_scale:     f64,
+	pub branch_scale:      f64,
 	/// For every 1 block the branch is long, this multiplier determines how many blocks it will go down on the trunk.
-	pub branch_slope:     f64,
-	/// Default height of the leaves of the foliage clusters, from top to bottom.
-	pub foliage_height:   i32
+	pub branch_slope:      f64,
+	pub min_branch_height: f64,
+	/// Height of the leaves of the foliage clusters, from top to bottom.
+	pub foliage_height:    i32
 }
 
 impl LargeTree {
-	/// Computes the spread at a given Y value. This is computed using the spread_center and spread_center_sq.
-	pub fn spread(&self, y: i32) -> f64 {
-		let distance_from_center = self.spread_center - (y - self.orgin.1 + 1) as f32;
-		((self.spread_center_sq - distance_from_center.powi(2)).sqrt() * 0.5) as f64
-	}
-	
-	// TODO: Replace this with an iterator implementation?
-	/// Gets the foliage at a given Y level. The caller is responsible for ordering the calls, managing the Y value, and creating the random number generator.
-	pub fn foliage(&self, y: i32, spread: f64, rng: &mut Random) -> Foilage {
-		let branch_factor = self.branch_scale * spread * (rng.next_f32() as f64 + 0.328);
+	/// Computes how far the foliage clusters spread out from the trunk on a layer, where layer 0 is the bottom of the tree.
+	/// The spread is largest at half of the height of the tree. The lower 30% of the tree has no foliage, returning None.
+	pub fn spread(&self, layer: i32) -> Option<f32> {
+		if (layer as f64) < (self.height as f32 as f64) * 0.3 {
+			return None;
+		}
+
+		let half = self.height as f32 / 2.0;
+		let distance = half - layer as f32;
+
+		let spread = if distance == 0.0 {
+			half
+		} else if distance.abs() >= half {
+			0.0
+		} else {
+			((half as f64).powi(2) - (distance.abs() as f64).powi(2)).sqrt() as f32
+		};
+
+		Some(spread * 0.5)
+	}
+
+	/// Gets a foliage cluster at a given Y level. The caller is responsible for ordering the calls, managing the Y value,
+	/// and checking that the cluster and its branch are not obstructed.
+	pub fn foliage(&self, y: i32, spread: f32, rng: &mut Random) -> Foliage {
+		let branch_factor = self.branch_scale * (spread as f64) * (rng.next_f32() as f64 + 0.328);
 		let angle = (rng.next_f32() as f64) * TAU;
-		
-		let cluster = (
-			(branch_factor * angle.sin() + (self.orgin.0 as f64) + 0.5).floor() as i32,
+
+		let position = (
+			(branch_factor * angle.sin() + (self.origin.0 as f64) + 0.5).floor() as i32,
 			y,
-			(branch_factor * angle.cos() + (self.orgin.2 as f64) + 0.5).floor() as i32
+			(branch_factor * angle.cos() + (self.origin.2 as f64) + 0.5).floor() as i32
 		);
-		
-		let foliage_top_y = y + self.foliage_height;
-		
+
 		let trunk_distance = (
-			(self.orgin.0 - cluster.0) as f64,
-			(self.orgin.2 - cluster.2) as f64
+			(self.origin.0 - position.0) as f64,
+			(self.origin.2 - position.2) as f64
 		);
-		
+
 		let branch_length = (trunk_distance.0 * trunk_distance.0 + trunk_distance.1 * trunk_distance.1).sqrt();
-		
+
 		// Determine how low to place the branch start Y, controlled by branch_slope. Longer branches have lower starts on the trunk.
 		let slope = branch_length * self.branch_slope;
-		
+
 		// Make sure the starting Y value for the branch is not above the trunk.
 		// Interestingly, it does not check whether the branch starts below the trunk.
-		let branch_y = ((y as f64) - slope).min(self.trunk_top as f64) as i32;
-		
-		// TODO: CheckLine from Cluster to TopY, and from BranchY to Cluster.
-		Foilage { cluster, foliage_top_y, branch_y }
+		let branch_y = if (y as f64) - slope > self.trunk_top as f64 {
+			self.trunk_top
+		} else {
+			((y as f64) - slope) as i32
+		};
+
+		Foliage { position, branch_y }
+	}
+
+	/// Radius of a layer of a foliage cluster, where 0 is the bottom layer. The top and bottom layers are smaller.
+	pub fn foliage_radius(&self, y_offset: i32) -> f32 {
+		if y_offset == 0 || y_offset == self.foliage_height - 1 { 2.0 } else { 3.0 }
+	}
+
+	/// Whether a branch starting at the Y coordinate is high enough on the trunk to be placed.
+	pub fn needs_branch(&self, branch_y: i32) -> bool {
+		((branch_y - self.origin.1) as f64) >= (self.height as f64) * self.min_branch_height
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{LargeTreeDecorator, LargeTreeSettings};
+	use super::line::Line;
+	use java_rand::Random;
+	use vocs::position::{GlobalColumnPosition, QuadPosition};
+	use decorator::{Decorator, Context};
+	use decorator::quad::Quad;
+
+	const SEED: u64 = 8675309;
+	const ORIGIN: (i32, i32, i32) = (16, 64, 16);
+	const LOG: u16 = 17*16;
+	const FOLIAGE: u16 = 18*16;
+	const STONE: u16 = 1*16;
+
+	fn origin() -> QuadPosition {
+		QuadPosition::new(ORIGIN.0 as u8, ORIGIN.1 as u8, ORIGIN.2 as u8)
+	}
+
+	fn fixed_height(height: i32) -> LargeTreeDecorator<u16> {
+		LargeTreeDecorator {
+			settings: LargeTreeSettings {
+				min_height: height,
+				add_height: 0,
+				.. LargeTreeSettings::default()
+			},
+			.. LargeTreeDecorator::default()
+		}
+	}
+
+	#[test]
+	fn test_generate() {
+		let decorator = LargeTreeDecorator::default();
+
+		let mut world = ::test::quad_world(2*16, 64);
+		let biomes = ::test::plains();
+		let context = Context::new(GlobalColumnPosition::new(0, 0), &biomes);
+		let mut quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		// Replicate the shape RNG to find out which clusters the decorator is going to pick.
+		let mut shape = Random::new(Random::new(SEED).next_u64());
+		let height = decorator.settings.min_height + shape.next_i32_bound(decorator.settings.add_height + 1);
+		let tree = decorator.settings.tree(ORIGIN, height);
+		let clusters = decorator.clusters(&mut quad, &mut shape, &tree);
+
+		decorator.generate(&mut quad, &mut Random::new(SEED), origin(), &context).unwrap();
+
+		for y in ORIGIN.1..(tree.trunk_top + 1) {
+			assert_eq!(*quad.get(QuadPosition::new(16, y as u8, 16)), LOG, "trunk at Y={}", y);
+		}
+
+		// The top of the cluster centered on the trunk is above the trunk.
+		assert_eq!(*quad.get(QuadPosition::new(16, (ORIGIN.1 + height - 1) as u8, 16)), FOLIAGE);
+
+		for cluster in &clusters {
+			for y_offset in 0..tree.foliage_height {
+				let center = quad.get(QuadPosition::new(cluster.position.0 as u8, (cluster.position.1 + y_offset) as u8, cluster.position.2 as u8));
+
+				assert!(*center == FOLIAGE || *center == LOG, "cluster {:?} has {} at offset {}", cluster, center, y_offset);
+			}
+
+			if tree.needs_branch(cluster.branch_y) {
+				for (x, y, z) in Line::new((ORIGIN.0, cluster.branch_y, ORIGIN.2), cluster.position).draw() {
+					assert_eq!(*quad.get(QuadPosition::new(x as u8, y as u8, z as u8)), LOG, "branch of cluster {:?}", cluster);
+				}
+			}
+		}
+
+		assert!(quad.spilled().is_empty());
+	}
+
+	#[test]
+	fn test_obstructed_tree_shortened() {
+		let decorator = fixed_height(16);
+
+		let mut world = ::test::quad_world(2*16, 64);
+		let biomes = ::test::plains();
+		let context = Context::new(GlobalColumnPosition::new(0, 0), &biomes);
+		let mut quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		// 10 blocks of clear space above the origin, so the tree is shortened from 16 to 10.
+		quad.set_immediate(QuadPosition::new(16, 74, 16), &STONE);
+
+		decorator.generate(&mut quad, &mut Random::new(SEED), origin(), &context).unwrap();
+
+		// The trunk of a tree with a height of 10 ends at 64 + floor(10 * 0.618).
+		assert_eq!(*quad.get(QuadPosition::new(16, 70, 16)), LOG);
+		assert_eq!(*quad.get(QuadPosition::new(16, 71, 16)), FOLIAGE);
+		assert_eq!(*quad.get(QuadPosition::new(16, 73, 16)), FOLIAGE);
+		assert_eq!(*quad.get(QuadPosition::new(16, 74, 16)), STONE);
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn test_obstructed_tree_rejected() {
+		let decorator = fixed_height(16);
+
+		let mut world = ::test::quad_world(2*16, 64);
+		let biomes = ::test::plains();
+		let context = Context::new(GlobalColumnPosition::new(0, 0), &biomes);
+		let mut quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		// Only 4 blocks of clear space, below the minimum of 6.
+		quad.set_immediate(QuadPosition::new(16, 68, 16), &STONE);
+
+		decorator.generate(&mut quad, &mut Random::new(SEED), origin(), &context).unwrap();
+
+		for y in 64..68 {
+			assert_eq!(*quad.get(QuadPosition::new(16, y, 16)), 0);
+		}
+
+		assert!(quad.spilled().is_empty());
+	}
+
+	#[test]
+	fn test_obstructed_clusters_dropped() {
+		let decorator = LargeTreeDecorator::default();
+		let tree = decorator.settings.tree(ORIGIN, 16);
+
+		let mut clear_world = ::test::quad_world(2*16, 64);
+		let mut clear_quad = Quad::new(clear_world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+		let clear = decorator.clusters(&mut clear_quad, &mut Random::new(SEED), &tree);
+
+		// 2 clusters on each of the 7 layers with foliage, plus the cluster at the top of the trunk.
+		assert_eq!(tree.foliage_per_y, 2);
+		assert_eq!(clear.len(), 15);
+
+		// A ceiling at Y=74 obstructs every cluster starting above Y=68. The top cluster is never checked.
+		let mut world = ::test::quad_world(2*16, 64);
+		let mut quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		for x in 0..32 {
+			for z in 0..32 {
+				quad.set_immediate(QuadPosition::new(x, 74, z), &STONE);
+			}
+		}
+
+		let obstructed = decorator.clusters(&mut quad, &mut Random::new(SEED), &tree);
+
+		assert_eq!(obstructed.len(), 3);
+		assert_eq!(obstructed[0].position, clear[0].position);
+
+		let expected = clear[1..].iter()
+			.filter(|cluster| cluster.position.1 == 68)
+			.map(|cluster| (cluster.position, cluster.branch_y))
+			.collect::<Vec<_>>();
+
+		let actual = obstructed[1..].iter()
+			.map(|cluster| (cluster.position, cluster.branch_y))
+			.collect::<Vec<_>>();
+
+		assert_eq!(actual, expected);
+	}
+
+	#[test]
+	fn test_needs_branch() {
+		// Branches need to start at least 20% of the height above the origin: 2 blocks for a tree of height 10.
+		let tree = LargeTreeSettings::default().tree(ORIGIN, 10);
+
+		assert!(tree.needs_branch(66));
+		assert!(!tree.needs_branch(65));
+	}
+}
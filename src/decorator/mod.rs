@@ -1,40 +1,259 @@
 use java_rand::Random;
-use vocs::view::QuadMut;
-use vocs::position::{ColumnPosition, QuadPosition};
+use self::quad::Quad;
+use self::condition::Condition;
+use self::height::HeightDistribution;
+use vocs::position::{QuadPosition, GlobalColumnPosition};
 use vocs::indexed::Target;
 use distribution::Distribution;
+use biome::Biome;
+use biome::climate::Climate;
+use biome::source::BiomeSource;
+use block::BlockRegistry;
 use serde_json;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::marker::PhantomData;
 
+pub mod quad;
+pub mod condition;
+pub mod height;
+pub mod biome;
 pub mod dungeon;
 pub mod vein;
 pub mod clump;
 pub mod large_tree;
 pub mod lake;
 pub mod tree;
+pub mod conifer;
 pub mod exposed;
+pub mod overworld_173;
 
-// TODO: MultiDispatcher
-
+/// Error returned by decorators. None of them can currently fail: blocks placed outside of the quad are spilled
+/// with `Quad::set_or_spill`, and attempts that would need to read blocks there are skipped, so this has no variants.
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
-pub struct Spilled(pub QuadPosition);
-pub type Result = ::std::result::Result<(), Spilled>;
+pub enum Error {}
+pub type Result = ::std::result::Result<(), Error>;
+
+/// The chunk that is being populated, passed to dispatchers and decorators so that they can depend on the biome and
+/// climate. Like the Notchian generator, `biome` and `climate` are taken from the block at the +XZ corner of the quad,
+/// while `biome_at` and `climate_at` look up the values at the decoration site.
+pub struct Context<'a, B> where B: 'a + Target {
+	/// Column at the lower corner of the quad.
+	pub column: GlobalColumnPosition,
+	pub biome: &'a Biome<B>,
+	pub climate: Climate,
+	pub biomes: &'a BiomeSource<B>
+}
+
+impl<'a, B> Context<'a, B> where B: 'a + Target {
+	/// Creates the context for the quad of a column, looking up the biome and climate at the +XZ corner of the quad.
+	pub fn new(column: GlobalColumnPosition, biomes: &'a BiomeSource<B>) -> Self {
+		let (x, z) = (column.x() * 16 + 16, column.z() * 16 + 16);
+
+		Context {
+			column,
+			biome: biomes.biome_at(x, z),
+			climate: biomes.climate_at(x, z),
+			biomes
+		}
+	}
+
+	/// Converts a position within the quad to absolute block coordinates.
+	pub fn absolute(&self, position: QuadPosition) -> (i32, i32, i32) {
+		(
+			self.column.x() * 16 + position.x() as i32,
+			position.y() as i32,
+			self.column.z() * 16 + position.z() as i32
+		)
+	}
+
+	/// Looks up the biome at a position within the quad.
+	pub fn biome_at(&self, position: QuadPosition) -> &'a Biome<B> {
+		let (x, _, z) = self.absolute(position);
+
+		self.biomes.biome_at(x, z)
+	}
+
+	/// Samples the climate at a position within the quad.
+	pub fn climate_at(&self, position: QuadPosition) -> Climate {
+		let (x, _, z) = self.absolute(position);
+
+		self.biomes.climate_at(x, z)
+	}
+}
 
-pub struct Dispatcher<H, R, B> where H: Distribution, R: Distribution, B: Target {
+pub struct Dispatcher<H, R, B> where H: HeightDistribution<B>, R: Distribution, B: Target {
 	pub height_distribution: H,
 	pub rarity: R,
+	/// Checked in order after picking each position, the decorator is only run if all of them pass.
+	pub conditions: Vec<Condition<B>>,
+	pub decorator: Box<Decorator<B>>
+}
+
+impl<H, R, B> Dispatcher<H, R, B> where H: HeightDistribution<B>, R: Distribution, B: Target {
+	pub fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, context: &Context<B>) -> Result {
+		for _ in 0..self.rarity.next(rng) {
+			let position = match self.height_distribution.position(quad, rng) {
+				Some(position) => QuadPosition::from_centered(position),
+				None => continue
+			};
+			
+			if !condition::check_all(&self.conditions, quad, rng, position, context) {
+				continue;
+			}
+			
+			self.decorator.generate(quad, rng, position, context)?;
+		}
+		
+		Ok(())
+	}
+}
+
+/// When a `MultiDispatcher` picks its decorator, relative to picking the position of the attempt.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum SelectionOrder {
+	/// Picks the decorator, then the X, Y, and Z coordinates.
+	BeforePosition,
+	/// Picks the X, Y, and Z coordinates, then the decorator. Notchian trees pick their kind after the X and Z
+	/// coordinates, which matches this ordering when using a `Heightmap`, since it does not use the RNG for the Y.
+	AfterPosition
+}
+
+impl Default for SelectionOrder {
+	fn default() -> Self {
+		SelectionOrder::AfterPosition
+	}
+}
+
+/// How a `MultiDispatcher` picks one of its choices.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Selection {
+	/// Picks a choice with a probability proportional to its weight, using a single `rand(total weight)` call.
+	Weighted,
+	/// Tests each choice in order with a 1 in `chance` roll, picking the first one that passes.
+	/// A chance of 1 does not call the RNG and always passes, so it is used for the fallback choice.
+	/// If no choice passes, the attempt does nothing.
+	Ordered
+}
+
+impl Default for Selection {
+	fn default() -> Self {
+		Selection::Weighted
+	}
+}
+
+pub struct Choice<B> where B: Target {
+	/// Weight of this choice, used by `Selection::Weighted`.
+	pub weight: u32,
+	/// Chance of this choice as probability = 1 / chance, used by `Selection::Ordered`.
+	pub chance: u32,
 	pub decorator: Box<Decorator<B>>
 }
 
-impl<H, R, B> Dispatcher<H, R, B> where H: Distribution, R: Distribution, B: Target {
-	pub fn generate(&self, quad: &mut QuadMut<B>, rng: &mut Random) -> Result {
+/// Like a `Dispatcher`, but picks one of several decorators for each attempt, such as big oak trees vs. oak trees.
+pub struct MultiDispatcher<H, R, B> where H: HeightDistribution<B>, R: Distribution, B: Target {
+	pub height_distribution: H,
+	pub rarity: R,
+	pub selection: Selection,
+	pub order: SelectionOrder,
+	/// Checked in order after picking each position, but before picking the decorator if it is picked after the
+	/// position. The decorator is only run if all of them pass.
+	pub conditions: Vec<Condition<B>>,
+	pub choices: Vec<Choice<B>>
+}
+
+impl<H, R, B> MultiDispatcher<H, R, B> where H: HeightDistribution<B>, R: Distribution, B: Target {
+	pub fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, context: &Context<B>) -> Result {
 		for _ in 0..self.rarity.next(rng) {
-			let at = ColumnPosition::new(
-				rng.next_u32_bound(16) as u8,
-				self.height_distribution.next(rng) as u8,
-				rng.next_u32_bound(16) as u8
-			);
+			let mut decorator = None;
+			
+			if self.order == SelectionOrder::BeforePosition {
+				decorator = self.select(rng);
+			}
 			
-			self.decorator.generate(quad, rng, QuadPosition::from_centered(at))?;
+			let position = match self.height_distribution.position(quad, rng) {
+				Some(position) => QuadPosition::from_centered(position),
+				None => continue
+			};
+			
+			if !condition::check_all(&self.conditions, quad, rng, position, context) {
+				continue;
+			}
+			
+			if self.order == SelectionOrder::AfterPosition {
+				decorator = self.select(rng);
+			}
+			
+			if let Some(decorator) = decorator {
+				decorator.generate(quad, rng, position, context)?;
+			}
+		}
+		
+		Ok(())
+	}
+	
+	fn select(&self, rng: &mut Random) -> Option<&Decorator<B>> {
+		match self.selection {
+			Selection::Weighted => {
+				let total = self.choices.iter().map(|choice| choice.weight).sum::<u32>();
+				
+				if total == 0 {
+					return None;
+				}
+				
+				let mut roll = rng.next_u32_bound(total);
+				
+				for choice in &self.choices {
+					if roll < choice.weight {
+						return Some(&*choice.decorator);
+					}
+					
+					roll -= choice.weight;
+				}
+				
+				None
+			},
+			Selection::Ordered => {
+				for choice in &self.choices {
+					if choice.chance <= 1 || rng.next_u32_bound(choice.chance) == 0 {
+						return Some(&*choice.decorator);
+					}
+				}
+				
+				None
+			}
+		}
+	}
+}
+
+/// Decorates an entire quad, with knowledge of which column is being populated.
+/// Used for pipelines that make decisions per chunk, such as looking up the biome.
+pub trait Populator<B> where B: Target {
+	fn populate(&self, quad: &mut Quad<B>, rng: &mut Random, column: GlobalColumnPosition) -> Result;
+}
+
+/// Runs a decorator some amount of times in a quad. Implemented by `Dispatcher` and `MultiDispatcher`, so that
+/// both can be stored in the same decorator set.
+pub trait Dispatch<B> where B: Target {
+	fn dispatch(&self, quad: &mut Quad<B>, rng: &mut Random, context: &Context<B>) -> Result;
+}
+
+impl<H, R, B> Dispatch<B> for Dispatcher<H, R, B> where H: HeightDistribution<B>, R: Distribution, B: Target {
+	fn dispatch(&self, quad: &mut Quad<B>, rng: &mut Random, context: &Context<B>) -> Result {
+		self.generate(quad, rng, context)
+	}
+}
+
+impl<H, R, B> Dispatch<B> for MultiDispatcher<H, R, B> where H: HeightDistribution<B>, R: Distribution, B: Target {
+	fn dispatch(&self, quad: &mut Quad<B>, rng: &mut Random, context: &Context<B>) -> Result {
+		self.generate(quad, rng, context)
+	}
+}
+
+impl<B> Populator<B> for Vec<Box<Populator<B>>> where B: Target {
+	fn populate(&self, quad: &mut Quad<B>, rng: &mut Random, column: GlobalColumnPosition) -> Result {
+		for populator in self {
+			populator.populate(quad, rng, column)?;
 		}
 		
 		Ok(())
@@ -42,9 +261,207 @@ impl<H, R, B> Dispatcher<H, R, B> where H: Distribution, R: Distribution, B: Tar
 }
 
 pub trait Decorator<B> where B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, rng: &mut Random, position: QuadPosition) -> Result;
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, context: &Context<B>) -> Result;
+}
+
+impl<B> Decorator<B> for Box<Decorator<B>> where B: Target {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, context: &Context<B>) -> Result {
+		(**self).generate(quad, rng, position, context)
+	}
 }
 
 pub trait DecoratorFactory<B> where B: Target {
 	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>>;
-}
\ No newline at end of file
+}
+
+/// Creates a registry containing a factory for every built-in decorator, keyed by the name used in decorator configs.
+/// Some factories use the properties of the blocks for defaults, such as the blocks that tree foliage replaces.
+pub fn default_registry<B>(blocks: &BlockRegistry<B>) -> HashMap<String, Box<DecoratorFactory<B>>> where B: 'static + Target + Deserialize {
+	let mut registry: HashMap<String, Box<DecoratorFactory<B>>> = HashMap::new();
+	
+	registry.insert("vein".to_string(),             Box::new(vein::VeinDecoratorFactory::default()));
+	registry.insert("seaside_vein".to_string(),     Box::new(vein::SeasideVeinDecoratorFactory::default()));
+	registry.insert("lake".to_string(),             Box::new(lake::LakeDecoratorFactory::default()));
+	registry.insert("dungeon".to_string(),          Box::new(dungeon::DungeonDecoratorFactory::default()));
+	registry.insert("plant".to_string(),            Box::new(clump::plant::PlantDecoratorFactory::default()));
+	registry.insert("cactus".to_string(),           Box::new(clump::cactus::CactusDecoratorFactory::default()));
+	registry.insert("sugar_cane".to_string(),       Box::new(clump::sugar_cane::SugarCaneDecoratorFactory::default()));
+	registry.insert("exposed".to_string(),          Box::new(exposed::ExposedDecoratorFactory::default()));
+	registry.insert("tree".to_string(),             Box::new(tree::TreeDecoratorFactory::new(blocks)));
+	registry.insert("large_tree".to_string(),       Box::new(large_tree::LargeTreeDecoratorFactory::default()));
+	registry.insert("spruce".to_string(),           Box::new(conifer::SpruceDecoratorFactory::new(blocks)));
+	registry.insert("pine".to_string(),             Box::new(conifer::PineDecoratorFactory::new(blocks)));
+	
+	registry.insert("plant_clump".to_string(),      Box::new(clump::ClumpDecoratorFactory(clump::plant::PlantDecoratorFactory::default(), PhantomData)));
+	registry.insert("cactus_clump".to_string(),     Box::new(clump::ClumpDecoratorFactory(clump::cactus::CactusDecoratorFactory::default(), PhantomData)));
+	registry.insert("sugar_cane_clump".to_string(), Box::new(clump::FlatClumpDecoratorFactory(clump::sugar_cane::SugarCaneDecoratorFactory::default(), PhantomData)));
+	
+	registry
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Decorator, Context, Result, MultiDispatcher, Choice, Selection, SelectionOrder};
+	use super::quad::Quad;
+	use java_rand::Random;
+	use vocs::position::{GlobalColumnPosition, QuadPosition};
+
+	/// Marks the position of each attempt with its own block, so that tests can tell which choice was picked.
+	struct Marker(u16);
+
+	impl Decorator<u16> for Marker {
+		fn generate(&self, quad: &mut Quad<u16>, _: &mut Random, position: QuadPosition, _: &Context<u16>) -> Result {
+			quad.set_immediate(position, &self.0);
+
+			Ok(())
+		}
+	}
+
+	/// Creates a dispatcher with a single attempt at Y=64, where each choice is a (weight, chance) pair that
+	/// places a marker numbered after its index, starting at 1.
+	fn dispatcher(selection: Selection, order: SelectionOrder, choices: &[(u32, u32)]) -> MultiDispatcher<u32, u32, u16> {
+		MultiDispatcher {
+			height_distribution: 64,
+			rarity: 1,
+			selection,
+			order,
+			conditions: vec![],
+			choices: choices.iter().enumerate().map(|(index, &(weight, chance))| Choice {
+				weight,
+				chance,
+				decorator: Box::new(Marker(index as u16 + 1)) as Box<Decorator<u16>>
+			}).collect()
+		}
+	}
+
+	/// Runs the dispatcher, returning the quad X, Z, and marker of the placed block, along with the RNG afterwards.
+	fn run(dispatcher: &MultiDispatcher<u32, u32, u16>, seed: u64) -> (Option<(u8, u8, u16)>, Random) {
+		let mut world = ::test::quad_world(0, 0);
+		let biomes = ::test::plains();
+		let context = Context::new(GlobalColumnPosition::new(0, 0), &biomes);
+		let mut quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		let mut rng = Random::new(seed);
+		dispatcher.generate(&mut quad, &mut rng, &context).unwrap();
+
+		let mut placed = None;
+
+		for x in 0..32 {
+			for z in 0..32 {
+				let block = *quad.get(QuadPosition::new(x, 64, z));
+
+				if block != 0 {
+					assert_eq!(placed, None, "more than one block was placed");
+					placed = Some((x, z, block));
+				}
+			}
+		}
+
+		(placed, rng)
+	}
+
+	#[test]
+	fn test_weighted_boundaries() {
+		let dispatcher = dispatcher(Selection::Weighted, SelectionOrder::AfterPosition, &[(3, 0), (0, 0), (2, 0)]);
+		let mut rolls = Vec::new();
+
+		for seed in 0..64 {
+			let mut rng = Random::new(seed);
+			let x = rng.next_u32_bound(16) as u8 + 8;
+			let z = rng.next_u32_bound(16) as u8 + 8;
+			let roll = rng.next_u32_bound(5);
+
+			// Rolls 0 to 2 pick the first choice, and 3 to 4 pick the last. The choice with no weight is never picked.
+			let marker = if roll < 3 { 1 } else { 3 };
+
+			let (placed, mut after) = run(&dispatcher, seed);
+
+			assert_eq!(placed, Some((x, z, marker)), "seed {} rolled {}", seed, roll);
+			assert_eq!(after.next_u64(), rng.next_u64());
+
+			rolls.push(roll);
+		}
+
+		assert!(rolls.contains(&2) && rolls.contains(&3));
+	}
+
+	#[test]
+	fn test_weighted_zero_total() {
+		let dispatcher = dispatcher(Selection::Weighted, SelectionOrder::AfterPosition, &[(0, 0), (0, 0)]);
+
+		for seed in 0..16 {
+			let mut rng = Random::new(seed);
+			rng.next_u32_bound(16);
+			rng.next_u32_bound(16);
+
+			// Nothing is placed, and no roll is made for the selection.
+			let (placed, mut after) = run(&dispatcher, seed);
+
+			assert_eq!(placed, None);
+			assert_eq!(after.next_u64(), rng.next_u64());
+		}
+	}
+
+	#[test]
+	fn test_ordered() {
+		let fallback_last = dispatcher(Selection::Ordered, SelectionOrder::AfterPosition, &[(0, 4), (0, 1)]);
+		let fallback_first = dispatcher(Selection::Ordered, SelectionOrder::AfterPosition, &[(0, 1), (0, 4)]);
+		let mut rolls = Vec::new();
+
+		for seed in 0..64 {
+			let mut rng = Random::new(seed);
+			let x = rng.next_u32_bound(16) as u8 + 8;
+			let z = rng.next_u32_bound(16) as u8 + 8;
+			let position_rng = rng.clone();
+
+			// The fallback with a chance of 1 does not make a roll of its own.
+			let roll = rng.next_u32_bound(4);
+			let marker = if roll == 0 { 1 } else { 2 };
+
+			let (placed, mut after) = run(&fallback_last, seed);
+
+			assert_eq!(placed, Some((x, z, marker)), "seed {} rolled {}", seed, roll);
+			assert_eq!(after.next_u64(), rng.next_u64());
+
+			// When the first choice has a chance of 1, no roll is made at all.
+			let mut rng = position_rng;
+			let (placed, mut after) = run(&fallback_first, seed);
+
+			assert_eq!(placed, Some((x, z, 1)));
+			assert_eq!(after.next_u64(), rng.next_u64());
+
+			rolls.push(roll);
+		}
+
+		assert!(rolls.contains(&0) && rolls.iter().any(|&roll| roll != 0));
+	}
+
+	#[test]
+	fn test_selection_order() {
+		let before = dispatcher(Selection::Weighted, SelectionOrder::BeforePosition, &[(1, 0), (1, 0)]);
+		let after = dispatcher(Selection::Weighted, SelectionOrder::AfterPosition, &[(1, 0), (1, 0)]);
+		let mut differ = false;
+
+		for seed in 0..16 {
+			let mut rng = Random::new(seed);
+			let roll = rng.next_u32_bound(2);
+			let x = rng.next_u32_bound(16) as u8 + 8;
+			let z = rng.next_u32_bound(16) as u8 + 8;
+
+			let expected_before = Some((x, z, roll as u16 + 1));
+			assert_eq!(run(&before, seed).0, expected_before);
+
+			let mut rng = Random::new(seed);
+			let x = rng.next_u32_bound(16) as u8 + 8;
+			let z = rng.next_u32_bound(16) as u8 + 8;
+			let roll = rng.next_u32_bound(2);
+
+			let expected_after = Some((x, z, roll as u16 + 1));
+			assert_eq!(run(&after, seed).0, expected_after);
+
+			differ |= expected_before != expected_after;
+		}
+
+		assert!(differ);
+	}
+}
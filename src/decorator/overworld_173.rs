@@ -0,0 +1,640 @@
+//! Population of the Beta 1.7.3 overworld. Every decorator is run in the same order as the Notchian generator,
+//! so that the population RNG is consumed in the same way.
+
+use java_rand::Random;
+use vocs::indexed::Target;
+use vocs::view::QuadMut;
+use decorator::quad::Quad;
+use vocs::position::{ColumnPosition, QuadPosition, GlobalColumnPosition, Offset, dir};
+use cgmath::Vector3;
+use biome::source::BiomeSource;
+use noise::octaves::PerlinOctaves;
+use block::{BlockRegistry, Liquid};
+use matcher::BlockMatcher;
+use distribution::{Chance, ChanceOrdering, Baseline, Linear, Packed2, Packed3Offset, Centered};
+use decorator::{Decorator, Dispatcher, Populator, Context, Result};
+use decorator::condition::Condition;
+use decorator::height::{Heightmap, HeightmapKind};
+use decorator::dungeon::{DungeonDecorator, DungeonBlocks, DungeonSettings};
+use decorator::dungeon::loot::LootTable;
+use decorator::lake::{LakeDecorator, LakeBlocks, LakeSettings};
+use decorator::vein::{VeinDecorator, SeasideVeinDecorator, VeinBlocks};
+use decorator::clump::{Clump, FlatClump};
+use decorator::clump::plant::PlantDecorator;
+use decorator::clump::cactus::{CactusDecorator, CactusBlocks, CactusSettings};
+use decorator::clump::sugar_cane::SugarCaneDecorator;
+use decorator::exposed::ExposedDecorator;
+use decorator::tree::TreeDecorator;
+use decorator::large_tree::LargeTreeDecorator;
+use decorator::conifer::{SpruceDecorator, PineDecorator};
+use std::marker::PhantomData;
+use std::collections::HashMap;
+
+/// Snow only considers the lower 128 blocks, the height of a Beta world.
+const HEIGHT_LIMIT: u8 = 128;
+
+type BaselineDispatcher<B> = Dispatcher<Chance<Baseline>, Chance<Baseline>, B>;
+
+/// Decoration amounts and tree selection of a biome, which the Notchian generator hardcodes for specific biomes.
+/// Configured per biome in `biomes.json`, biomes without a configuration get none.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Vegetation {
+	/// Whether the tree noise adds to the amount of trees.
+	#[serde(default)]
+	pub dense_trees: bool,
+	/// Added to the amount of trees. Negative values make trees rarer than the 1 in 10 chunks of other biomes.
+	#[serde(default)]
+	pub trees: i32,
+	#[serde(default)]
+	pub tree_kinds: TreeSelection,
+	#[serde(default)]
+	pub dandelions: u32,
+	#[serde(default)]
+	pub tall_grass: u32,
+	/// Whether 2 out of 3 tall grass clumps are replaced with ferns.
+	#[serde(default)]
+	pub ferns: bool,
+	#[serde(default)]
+	pub dead_bushes: u32,
+	#[serde(default)]
+	pub cacti: u32
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TreeKind {
+	Oak,
+	Large,
+	Birch,
+	Spruce,
+	Pine
+}
+
+/// How the kind of each tree is picked, like the `getRandomWorldGenForTrees` overrides of the Notchian biomes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TreeSelection {
+	/// 1 in 10 trees are large, the rest are oak trees.
+	Normal,
+	/// 1 in 5 trees are birches, and 1 in 3 of the rest are large.
+	Forest,
+	/// 1 in 3 trees are large, the rest are oak trees.
+	Rainforest,
+	/// 1 in 3 trees are pines, the rest are spruces.
+	Taiga
+}
+
+impl Default for TreeSelection {
+	fn default() -> Self {
+		TreeSelection::Normal
+	}
+}
+
+impl TreeSelection {
+	pub fn choose(&self, rng: &mut Random) -> TreeKind {
+		match *self {
+			TreeSelection::Normal     => if rng.next_u32_bound(10) == 0 { TreeKind::Large } else { TreeKind::Oak },
+			TreeSelection::Forest     => if rng.next_u32_bound(5) == 0 {
+				TreeKind::Birch
+			} else if rng.next_u32_bound(3) == 0 {
+				TreeKind::Large
+			} else {
+				TreeKind::Oak
+			},
+			TreeSelection::Rainforest => if rng.next_u32_bound(3) == 0 { TreeKind::Large } else { TreeKind::Oak },
+			TreeSelection::Taiga      => if rng.next_u32_bound(3) == 0 { TreeKind::Pine  } else { TreeKind::Spruce }
+		}
+	}
+}
+
+/// Chances of the optional parts of the pipeline, with the meaning of the customized settings of 1.8.
+/// A value of `None` disables that part.
+#[derive(Debug, Copy, Clone)]
+pub struct Features {
+	/// 1 in N chunks have a water lake. Must be at least 1.
+	pub water_lake_chance: Option<u32>,
+	/// 1 in N/10 chunks have a lava lake, which is only kept 1 in N/8 times above sea level. Must be at least 10.
+	pub lava_lake_chance: Option<u32>,
+	/// Amount of attempts at placing a dungeon in each chunk.
+	pub dungeon_chance: Option<u32>
+}
+
+impl Default for Features {
+	fn default() -> Self {
+		Features {
+			water_lake_chance: Some(4),
+			lava_lake_chance: Some(80),
+			dungeon_chance: Some(8)
+		}
+	}
+}
+
+pub struct SnowBlocks<B> where B: Target {
+	pub block: B,
+	/// Blocks that stop the search for the top of the column, solid blocks and liquids.
+	pub top: BlockMatcher<B>,
+	/// Blocks that snow can be placed on.
+	pub base: BlockMatcher<B>,
+	pub replace: BlockMatcher<B>
+}
+
+pub struct OverworldPopulator<B> where B: Target {
+	/// Biomes of the world. The biome of each chunk is taken from the block at the +XZ corner of the quad.
+	biomes: BiomeSource<B>,
+	/// Vegetation of each biome, keyed by the biome name.
+	vegetation: HashMap<String, Vegetation>,
+	tree_noise: PerlinOctaves,
+	/// Finds the height of a column like the Notchian heightmap, which trees are placed on.
+	heightmap: Heightmap<B>,
+	/// Blocks that tall grass and dead bushes search downwards through to find the ground.
+	above_ground: BlockMatcher<B>,
+	lakes: Vec<BaselineDispatcher<B>>,
+	dungeons: Vec<BaselineDispatcher<B>>,
+	veins: Vec<BaselineDispatcher<B>>,
+	trees: TreeDecorator<B>,
+	large_trees: LargeTreeDecorator<B>,
+	birches: TreeDecorator<B>,
+	spruces: SpruceDecorator<B>,
+	pines: PineDecorator<B>,
+	dandelions: Clump<PlantDecorator<B>, B>,
+	tall_grass: Clump<PlantDecorator<B>, B>,
+	ferns: Clump<PlantDecorator<B>, B>,
+	dead_bushes: Clump<PlantDecorator<B>, B>,
+	/// Roses, mushrooms, sugar cane, and pumpkins, which are the same in every biome.
+	plants: Vec<BaselineDispatcher<B>>,
+	cacti: Clump<CactusDecorator<B>, B>,
+	springs: Vec<BaselineDispatcher<B>>,
+	snow: SnowBlocks<B>
+}
+
+impl<B> OverworldPopulator<B> where B: Target {
+	/// Moves down from the position until the block is no longer `above_ground` or the bottom of the world is reached.
+	fn descend(&self, quad: &mut QuadMut<B>, position: QuadPosition) -> QuadPosition {
+		let mut y = position.y();
+
+		while y > 0 && self.above_ground.matches(quad.get(QuadPosition::new(position.x(), y, position.z()))) {
+			y -= 1;
+		}
+
+		QuadPosition::new(position.x(), y, position.z())
+	}
+
+	fn snow(&self, quad: &mut QuadMut<B>, context: &Context<B>) {
+		for x in 8..24 {
+			for z in 8..24 {
+				let top = match self.top_solid(quad, x, z) {
+					Some(top) => top,
+					None => continue
+				};
+
+				if top == 0 || top >= HEIGHT_LIMIT {
+					continue;
+				}
+
+				let at = QuadPosition::new(x, top, z);
+
+				// Higher terrain is colder.
+				let temperature = context.climate_at(at).temperature() - ((top as f64 - 64.0) / 64.0) * 0.3;
+
+				if temperature >= 0.5 {
+					continue;
+				}
+
+				let below = QuadPosition::new(x, top - 1, z);
+
+				if self.snow.replace.matches(quad.get(at)) && self.snow.base.matches(quad.get(below)) {
+					quad.set_immediate(at, &self.snow.block);
+				}
+			}
+		}
+	}
+
+	/// Returns the Y coordinate above the highest solid or liquid block, like `findTopSolidBlock`.
+	fn top_solid(&self, quad: &mut QuadMut<B>, x: u8, z: u8) -> Option<u8> {
+		(1..HEIGHT_LIMIT).rev()
+			.find(|&y| self.snow.top.matches(quad.get(QuadPosition::new(x, y, z))))
+			.map(|y| y + 1)
+	}
+}
+
+impl<B> Populator<B> for OverworldPopulator<B> where B: Target {
+	fn populate(&self, quad: &mut Quad<B>, rng: &mut Random, column: GlobalColumnPosition) -> Result {
+		let (block_x, block_z) = (column.x() * 16, column.z() * 16);
+
+		let context = Context::new(column, &self.biomes);
+		let vegetation = self.vegetation.get(&*context.biome.name).cloned().unwrap_or_else(Vegetation::default);
+
+		for dispatcher in &self.lakes {
+			dispatcher.generate(quad, rng, &context)?;
+		}
+
+		for dispatcher in &self.dungeons {
+			dispatcher.generate(quad, rng, &context)?;
+		}
+
+		for dispatcher in &self.veins {
+			dispatcher.generate(quad, rng, &context)?;
+		}
+
+		let noise = self.tree_noise.generate(Vector3::new(block_x as f64, block_z as f64, 0.0));
+		let dense_trees = ((noise / 8.0 + rng.next_f64() * 4.0 + 4.0) / 3.0) as i32;
+
+		let mut trees = if rng.next_u32_bound(10) == 0 { 1 } else { 0 };
+
+		if vegetation.dense_trees {
+			trees += dense_trees;
+		}
+
+		trees += vegetation.trees;
+
+		for _ in 0..trees {
+			let x = rng.next_u32_bound(16) as u8 + 8;
+			let z = rng.next_u32_bound(16) as u8 + 8;
+
+			let kind = vegetation.tree_kinds.choose(rng);
+
+			let position = match self.heightmap.height(quad, x, z) {
+				Some(y) => QuadPosition::new(x, y, z),
+				None => continue
+			};
+
+			match kind {
+				TreeKind::Oak    => self.trees.generate(quad, rng, position, &context)?,
+				TreeKind::Large  => self.large_trees.generate(quad, rng, position, &context)?,
+				TreeKind::Birch  => self.birches.generate(quad, rng, position, &context)?,
+				TreeKind::Spruce => self.spruces.generate(quad, rng, position, &context)?,
+				TreeKind::Pine   => self.pines.generate(quad, rng, position, &context)?
+			}
+		}
+
+		for _ in 0..vegetation.dandelions {
+			self.dandelions.generate(quad, rng, surface_position(rng), &context)?;
+		}
+
+		for _ in 0..vegetation.tall_grass {
+			let grass = if vegetation.ferns && rng.next_u32_bound(3) != 0 { &self.ferns } else { &self.tall_grass };
+			let position = self.descend(quad, surface_position(rng));
+
+			grass.generate(quad, rng, position, &context)?;
+		}
+
+		for _ in 0..vegetation.dead_bushes {
+			let position = self.descend(quad, surface_position(rng));
+
+			self.dead_bushes.generate(quad, rng, position, &context)?;
+		}
+
+		for dispatcher in &self.plants {
+			dispatcher.generate(quad, rng, &context)?;
+		}
+
+		for _ in 0..vegetation.cacti {
+			self.cacti.generate(quad, rng, surface_position(rng), &context)?;
+		}
+
+		for dispatcher in &self.springs {
+			dispatcher.generate(quad, rng, &context)?;
+		}
+
+		self.snow(quad, &context);
+
+		Ok(())
+	}
+}
+
+impl OverworldPopulator<u16> {
+	/// Creates the pipeline with the vanilla blocks, using the registry for the properties that the Notchian
+	/// decorators check, such as whether a block is solid. The vegetation of each biome is keyed by the biome name,
+	/// and dungeon chests are filled from `dungeon_loot`.
+	pub fn new(seed: u64, biomes: BiomeSource<u16>, blocks: &BlockRegistry<u16>, vegetation: HashMap<String, Vegetation>, features: Features, dungeon_loot: LootTable) -> Self {
+		let mut lakes = Vec::new();
+
+		if let Some(chance) = features.water_lake_chance {
+			lakes.push(Dispatcher {
+				decorator: Box::new(lake(blocks, 9*16, None)),
+				height_distribution: linear(0, 127),
+				rarity: one_in(chance),
+				conditions: Vec::new()
+			});
+		}
+
+		if let Some(chance) = features.lava_lake_chance {
+			// Lava lakes above sea level only generate 1 in 10 times by default, so that most of them end up underground.
+			lakes.push(Dispatcher {
+				decorator: Box::new(lake(blocks, 11*16, Some(1*16))),
+				height_distribution: Chance {
+					base: Baseline::Packed2(Packed2 { min: 0, linear_start: 8, max: 126 }),
+					ordering: ChanceOrdering::AlwaysGeneratePayload,
+					chance: 1
+				},
+				rarity: one_in(chance / 10),
+				conditions: vec![
+					Condition::Any { conditions: vec![
+						Condition::Below { y: 64 },
+						Condition::Chance { chance: chance / 8 }
+					]}
+				]
+			});
+		}
+
+		let mut dungeons = Vec::new();
+
+		if let Some(chance) = features.dungeon_chance {
+			dungeons.push(Dispatcher {
+				decorator: Box::new(DungeonDecorator {
+					blocks: DungeonBlocks {
+						carve:   0*16,
+						empty:   BlockMatcher::is(0*16),
+						solid:   blocks.solid(),
+						wall:    4*16,
+						floor:   48*16,
+						chest:   54*16,
+						spawner: 52*16
+					},
+					settings: DungeonSettings::default(),
+					loot: dungeon_loot
+				}),
+				height_distribution: linear(0, 127),
+				rarity: constant(chance),
+				conditions: Vec::new()
+			});
+		}
+
+		let veins = vec![
+			Dispatcher {
+				decorator: Box::new(SeasideVeinDecorator {
+					vein: VeinDecorator {
+						blocks: VeinBlocks {
+							replace: BlockMatcher::is(12*16),
+							block: 82*16
+						},
+						size: 32
+					},
+					ocean: blocks.liquid_of(Liquid::Water)
+				}),
+				height_distribution: linear(0, 127),
+				rarity: constant(10),
+				conditions: Vec::new()
+			},
+			vein( 3*16, 32, 20, linear(0, 127)),
+			vein(13*16, 32, 10, linear(0, 127)),
+			vein(16*16, 16, 20, linear(0, 127)),
+			vein(15*16,  8, 20, linear(0, 63)),
+			vein(14*16,  8,  2, linear(0, 31)),
+			vein(73*16,  7,  8, linear(0, 15)),
+			vein(56*16,  7,  1, linear(0, 15)),
+			vein(21*16,  6,  1, Chance {
+				base: Baseline::Centered(Centered { center: 16, radius: 16 }),
+				ordering: ChanceOrdering::AlwaysGeneratePayload,
+				chance: 1
+			})
+		];
+
+		let trees = TreeDecorator::default();
+		let birches = TreeDecorator::birch();
+		let spruces = SpruceDecorator::default();
+		let pines = PineDecorator::default();
+
+		let grass_base = BlockMatcher::include([2*16, 3*16, 60*16].iter());
+		let opaque = blocks.matcher(|properties| properties.solid && properties.opacity >= 15);
+
+		let plants = vec![
+			Dispatcher {
+				decorator: Box::new(clump(64, plant(38*16, grass_base.clone()))),
+				height_distribution: linear(0, 127),
+				rarity: one_in(2),
+				conditions: Vec::new()
+			},
+			Dispatcher {
+				decorator: Box::new(clump(64, plant(39*16, opaque.clone()))),
+				height_distribution: linear(0, 127),
+				rarity: one_in(4),
+				conditions: Vec::new()
+			},
+			Dispatcher {
+				decorator: Box::new(clump(64, plant(40*16, opaque))),
+				height_distribution: linear(0, 127),
+				rarity: one_in(8),
+				conditions: Vec::new()
+			},
+			Dispatcher {
+				decorator: Box::new(FlatClump {
+					iterations: 20,
+					horizontal: 4,
+					decorator: SugarCaneDecorator {
+						block: 83*16,
+						base: BlockMatcher::include([2*16, 3*16, 12*16].iter()),
+						liquid: blocks.liquid_of(Liquid::Water),
+						replace: BlockMatcher::is(0*16),
+						base_height: 2,
+						add_height: 2
+					},
+					phantom: PhantomData
+				}),
+				height_distribution: linear(0, 127),
+				rarity: constant(10),
+				conditions: Vec::new()
+			},
+			Dispatcher {
+				decorator: Box::new(clump(64, PumpkinDecorator {
+					facings: vec![86*16, 86*16 + 1, 86*16 + 2, 86*16 + 3],
+					base: BlockMatcher::is(2*16),
+					replace: BlockMatcher::is(0*16)
+				})),
+				height_distribution: linear(0, 127),
+				rarity: one_in(32),
+				conditions: Vec::new()
+			}
+		];
+
+		let springs = vec![
+			Dispatcher {
+				decorator: Box::new(spring(8*16)),
+				height_distribution: Chance {
+					base: Baseline::Packed2(Packed2 { min: 0, linear_start: 8, max: 126 }),
+					ordering: ChanceOrdering::AlwaysGeneratePayload,
+					chance: 1
+				},
+				rarity: constant(50),
+				conditions: Vec::new()
+			},
+			Dispatcher {
+				decorator: Box::new(spring(10*16)),
+				height_distribution: Chance {
+					base: Baseline::Packed3Offset(Packed3Offset { bound: 112, offset: 8 }),
+					ordering: ChanceOrdering::AlwaysGeneratePayload,
+					chance: 1
+				},
+				rarity: constant(20),
+				conditions: Vec::new()
+			}
+		];
+
+		// Snow does not form on ice.
+		let mut snow_base = blocks.solid();
+
+		if snow_base.blacklist {
+			snow_base.blocks.insert(79*16);
+		} else {
+			snow_base.blocks.remove(&(79*16));
+		}
+
+		OverworldPopulator {
+			biomes,
+			vegetation,
+			tree_noise: ::generator::overworld_173::tree_noise(seed),
+			heightmap: Heightmap::new(HeightmapKind::LightBlocking, blocks),
+			above_ground: BlockMatcher::include([0*16, 18*16, 18*16 + 1, 18*16 + 2].iter()),
+			lakes,
+			dungeons,
+			veins,
+			trees,
+			large_trees: LargeTreeDecorator::default(),
+			birches,
+			spruces,
+			pines,
+			dandelions: clump(64, plant(37*16, grass_base.clone())),
+			tall_grass: clump(128, plant(31*16 + 1, grass_base.clone())),
+			ferns: clump(128, plant(31*16 + 2, grass_base)),
+			dead_bushes: clump(4, plant(32*16, BlockMatcher::is(12*16))),
+			plants,
+			cacti: Clump {
+				iterations: 10,
+				horizontal: 8,
+				vertical: 4,
+				decorator: CactusDecorator {
+					blocks: CactusBlocks {
+						replace: BlockMatcher::is(0*16),
+						base: BlockMatcher::include([12*16, 81*16].iter()),
+						solid: blocks.solid(),
+						block: 81*16
+					},
+					settings: CactusSettings::default()
+				},
+				phantom: PhantomData
+			},
+			springs,
+			snow: SnowBlocks {
+				block: 78*16,
+				top: blocks.matcher(|properties| properties.solid || properties.liquid.is_some()),
+				base: snow_base,
+				replace: BlockMatcher::is(0*16)
+			}
+		}
+	}
+}
+
+/// Places a pumpkin facing in a random direction, which uses the RNG only after the position has been checked.
+struct PumpkinDecorator<B> where B: Target {
+	facings: Vec<B>,
+	base: BlockMatcher<B>,
+	replace: BlockMatcher<B>
+}
+
+impl<B> Decorator<B> for PumpkinDecorator<B> where B: Target {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
+		if !self.replace.matches(quad.get(position)) {
+			return Ok(());
+		}
+
+		match position.offset(dir::Down) {
+			Some(below) => if !self.base.matches(quad.get(below)) {
+				return Ok(())
+			},
+			None => return Ok(())
+		}
+
+		let facing = rng.next_u32_bound(self.facings.len() as u32) as usize;
+
+		quad.set_immediate(position, &self.facings[facing]);
+
+		Ok(())
+	}
+}
+
+/// Picks a position in the same way as most of the Notchian vegetation: a random X and Z in the centered chunk,
+/// and a random Y in the lower half of the world.
+fn surface_position(rng: &mut Random) -> QuadPosition {
+	let x = rng.next_u32_bound(16) as u8;
+	let y = rng.next_u32_bound(128) as u8;
+	let z = rng.next_u32_bound(16) as u8;
+
+	QuadPosition::from_centered(ColumnPosition::new(x, y, z))
+}
+
+fn constant(value: u32) -> Chance<Baseline> {
+	Chance {
+		base: Baseline::Constant { value },
+		ordering: ChanceOrdering::AlwaysGeneratePayload,
+		chance: 1
+	}
+}
+
+fn one_in(chance: u32) -> Chance<Baseline> {
+	Chance {
+		base: Baseline::Constant { value: 1 },
+		ordering: ChanceOrdering::CheckChanceBeforePayload,
+		chance
+	}
+}
+
+fn linear(min: u32, max: u32) -> Chance<Baseline> {
+	Chance {
+		base: Baseline::Linear(Linear { min, max }),
+		ordering: ChanceOrdering::AlwaysGeneratePayload,
+		chance: 1
+	}
+}
+
+fn lake(blocks: &BlockRegistry<u16>, liquid: u16, solidify: Option<u16>) -> LakeDecorator<u16> {
+	LakeDecorator {
+		blocks: LakeBlocks {
+			is_liquid:  blocks.liquid(),
+			is_solid:   blocks.solid(),
+			replacable: blocks.replaceable(),
+			liquid,
+			carve:      0*16,
+			solidify
+		},
+		settings: LakeSettings::default()
+	}
+}
+
+fn vein(block: u16, size: u32, count: u32, height_distribution: Chance<Baseline>) -> BaselineDispatcher<u16> {
+	Dispatcher {
+		decorator: Box::new(VeinDecorator {
+			blocks: VeinBlocks {
+				replace: BlockMatcher::is(1*16),
+				block
+			},
+			size
+		}),
+		height_distribution,
+		rarity: constant(count),
+		conditions: Vec::new()
+	}
+}
+
+fn plant(block: u16, base: BlockMatcher<u16>) -> PlantDecorator<u16> {
+	PlantDecorator {
+		block,
+		base,
+		replace: BlockMatcher::is(0*16)
+	}
+}
+
+fn clump<D>(iterations: u32, decorator: D) -> Clump<D, u16> where D: Decorator<u16> {
+	Clump {
+		iterations,
+		horizontal: 8,
+		vertical: 4,
+		decorator,
+		phantom: PhantomData
+	}
+}
+
+fn spring(block: u16) -> ExposedDecorator<u16> {
+	ExposedDecorator {
+		block,
+		stone: BlockMatcher::is(1*16),
+		empty: BlockMatcher::is(0*16)
+	}
+}
@@ -0,0 +1,149 @@
+use vocs::indexed::Target;
+use vocs::view::QuadMut;
+use vocs::position::QuadPosition;
+use entity::Entity;
+use entity::tile::TileEntity;
+use entity::tick::TileTick;
+use matcher::BlockMatcher;
+use std::ops::{Deref, DerefMut};
+
+/// The 2x2 quad of columns that decorators write into. Besides the blocks, this collects the tile entities, entities,
+/// and scheduled ticks created while decorating, which are stored in the chunk NBT instead of the block array.
+///
+/// Blocks written outside of the quad with `set_or_spill` are collected as well, so that they can be placed once the
+/// column containing them is available. Decoration attempts that would need to read blocks outside of the quad cannot
+/// be spilled, and are counted with `skip` instead.
+///
+/// Dereferences to the underlying `QuadMut` for reading and writing blocks.
+pub struct Quad<'q, B> where B: 'q + Target {
+	blocks: QuadMut<'q, B>,
+	extras: QuadExtras<B>,
+	spilled: Vec<SpilledBlock<B>>,
+	skipped: usize
+}
+
+impl<'q, B> Quad<'q, B> where B: 'q + Target {
+	pub fn new(blocks: QuadMut<'q, B>) -> Self {
+		Quad {
+			blocks,
+			extras: QuadExtras::default(),
+			spilled: Vec::new(),
+			skipped: 0
+		}
+	}
+
+	/// Places a block at a position relative to the lower corner of the quad, if the block currently there matches
+	/// `replace`. Positions outside of the quad on the X or Z axis are spilled: the block is recorded, and placed
+	/// once the column containing it is available. Positions below or above the world are discarded.
+	pub fn set_or_spill(&mut self, position: (i32, i32, i32), block: &B, replace: &BlockMatcher<B>) {
+		let (x, y, z) = position;
+
+		if y < 0 || y >= 256 {
+			return;
+		}
+
+		if x < 0 || x >= 32 || z < 0 || z >= 32 {
+			self.spilled.push(SpilledBlock {
+				position: (x, y as u8, z),
+				block: block.clone(),
+				replace: replace.clone()
+			});
+
+			return;
+		}
+
+		let position = QuadPosition::new(x as u8, y as u8, z as u8);
+
+		if replace.matches(self.blocks.get(position)) {
+			self.blocks.set_immediate(position, block);
+		}
+	}
+
+	/// Blocks spilled outside of the quad so far, in the order that they were placed.
+	pub fn spilled(&self) -> &[SpilledBlock<B>] {
+		&self.spilled
+	}
+
+	/// Removes the blocks spilled outside of the quad, returning them.
+	pub fn take_spilled(&mut self) -> Vec<SpilledBlock<B>> {
+		::std::mem::replace(&mut self.spilled, Vec::new())
+	}
+
+	/// Records a decoration attempt that was skipped because its position is outside of the quad.
+	pub fn skip(&mut self) {
+		self.skipped += 1;
+	}
+
+	/// Amount of decoration attempts skipped so far.
+	pub fn skipped(&self) -> usize {
+		self.skipped
+	}
+
+	/// Attaches a tile entity to the block at the position, replacing any tile entity previously added there.
+	pub fn add_tile_entity(&mut self, position: QuadPosition, tile_entity: TileEntity) {
+		self.extras.tile_entities.retain(|&(existing, _)| existing != position);
+		self.extras.tile_entities.push((position, tile_entity));
+	}
+
+	/// Adds an entity. The position of the entity is relative to the lower corner of the quad, and must be within the quad.
+	pub fn add_entity(&mut self, entity: Entity) {
+		self.extras.entities.push(entity);
+	}
+
+	/// Schedules an update of the block at the position.
+	pub fn schedule_tick(&mut self, position: QuadPosition, tick: TileTick<B>) {
+		self.extras.tile_ticks.push((position, tick));
+	}
+
+	pub fn extras(&self) -> &QuadExtras<B> {
+		&self.extras
+	}
+
+	/// Consumes the quad, returning everything added to it besides the blocks.
+	pub fn into_extras(self) -> QuadExtras<B> {
+		self.extras
+	}
+}
+
+impl<'q, B> Deref for Quad<'q, B> where B: 'q + Target {
+	type Target = QuadMut<'q, B>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.blocks
+	}
+}
+
+impl<'q, B> DerefMut for Quad<'q, B> where B: 'q + Target {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.blocks
+	}
+}
+
+/// Tile entities, entities, and scheduled ticks added to a quad, in the order that they were added.
+/// Positions are relative to the lower corner of the quad.
+#[derive(Debug, Clone)]
+pub struct QuadExtras<B> {
+	pub tile_entities: Vec<(QuadPosition, TileEntity)>,
+	pub entities: Vec<Entity>,
+	pub tile_ticks: Vec<(QuadPosition, TileTick<B>)>
+}
+
+impl<B> Default for QuadExtras<B> {
+	fn default() -> Self {
+		QuadExtras {
+			tile_entities: Vec::new(),
+			entities: Vec::new(),
+			tile_ticks: Vec::new()
+		}
+	}
+}
+
+/// A block placed outside of the quad, waiting for the column containing it to be available.
+#[derive(Debug, Clone)]
+pub struct SpilledBlock<B> where B: Target {
+	/// Position relative to the lower corner of the quad. The X and Z coordinates are outside of the quad.
+	pub position: (i32, u8, i32),
+	pub block: B,
+	/// The block is only placed if the block already there matches this.
+	pub replace: BlockMatcher<B>
+}
@@ -1,36 +1,72 @@
 use matcher::BlockMatcher;
+use block::BlockRegistry;
 use vocs::indexed::Target;
-use vocs::view::QuadMut;
+use decorator::quad::Quad;
 use vocs::position::{QuadPosition, Offset, dir};
-use decorator::{Decorator, Result};
+use decorator::{Decorator, DecoratorFactory, Context, Result};
 use java_rand::Random;
+use serde_json;
 
+/// Trees are not generated if any part of them would be above this Y coordinate, the height of a Beta world.
+const HEIGHT_LIMIT: u32 = 128;
+
+/// Block IDs of Beta 1.7.3 that are not opaque cubes, with any metadata. These are the blocks that
+/// `BlockRegistry::not_opaque` matches with the blocks.json of the `b173` profile.
+const NOT_OPAQUE_CUBES: [u16; 45] = [
+	0, 6, 8, 9, 10, 11, 18, 20, 26, 27, 28, 30, 31, 32, 37, 38, 39, 40, 50, 51, 52, 55, 59, 63, 64, 65, 66, 68, 69, 70,
+	71, 72, 75, 76, 77, 78, 79, 81, 83, 85, 90, 92, 93, 94, 96
+];
+
+/// Configures oak and birch trees. Configs that leave out `foliage_replace` get every block of the registry that is
+/// not an opaque cube.
+pub struct TreeDecoratorFactory<B> where B: Target {
+	not_opaque: BlockMatcher<B>
+}
+
+impl<B> TreeDecoratorFactory<B> where B: Target {
+	pub fn new(blocks: &BlockRegistry<B>) -> Self {
+		TreeDecoratorFactory {
+			not_opaque: blocks.not_opaque()
+		}
+	}
+}
+
+impl<B> DecoratorFactory<B> for TreeDecoratorFactory<B> where B: 'static + Target + ::serde::Deserialize {
+	fn configure(&self, config: serde_json::Value) -> serde_json::Result<Box<Decorator<B>>> {
+		let explicit = config.pointer("/blocks/foliage_replace").is_some();
+		let mut decorator = serde_json::from_value::<TreeDecorator<B>>(config)?;
+
+		if !explicit {
+			decorator.blocks.foliage_replace = self.not_opaque.clone();
+		}
+
+		Ok(Box::new(decorator))
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TreeDecorator<B> where B: Target {
-	blocks: TreeBlocks<B>,
-	settings: TreeSettings
+	pub blocks: TreeBlocks<B>,
+	#[serde(default)]
+	pub settings: TreeSettings
 }
 
 impl<B> Decorator<B> for TreeDecorator<B> where B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, rng: &mut Random, position: QuadPosition) -> Result {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, _: &Context<B>) -> Result {
 		let tree = self.settings.tree(rng, position);
 		
-		if tree.leaves_max_y > 128 {
+		if position.y() < 1 || tree.leaves_max_y >= HEIGHT_LIMIT {
 			return Ok(());
 		}
 
-		let below = match position.offset(dir::Down) {
-			Some(below) => below,
-			None => return Ok(())
-		};
+		if !self.blocks.is_clear(quad, position, tree.trunk_height, |y| tree.bounding_radius(y)) {
+			return Ok(());
+		}
 
-		if !self.blocks.soil.matches(quad.get(below)) {
+		if !self.blocks.place_soil(quad, position) {
 			return Ok(());
 		}
 		
-		// TODO: Check bounding box
-		
-		quad.set_immediate(below, &self.blocks.new_soil);
-		
 		quad.ensure_available(self.blocks.log.clone());
 		quad.ensure_available(self.blocks.foliage.clone());
 		
@@ -42,9 +78,9 @@ impl<B> Decorator<B> for TreeDecorator<B> where B: Target {
 		for y in tree.leaves_min_y..tree.leaves_max_y {
 			let radius = tree.foliage_radius(y) as i32;
 			
-			for z_offset in -radius..radius {
-				for x_offset in -radius..radius {
-					if z_offset.abs() != radius || x_offset.abs() != radius || rng.next_u32_bound(self.settings.foliage_corner_chance) != 0 && y < tree.trunk_top {
+			for x_offset in -radius..(radius + 1) {
+				for z_offset in -radius..(radius + 1) {
+					if x_offset.abs() != radius || z_offset.abs() != radius || rng.next_u32_bound(self.settings.foliage_corner_chance) != 0 && y < tree.trunk_top {
 
 						let position = match position.offset((x_offset as i8, 0, z_offset as i8)) {
 							Some(position) => position,
@@ -53,7 +89,9 @@ impl<B> Decorator<B> for TreeDecorator<B> where B: Target {
 
 						let position = QuadPosition::new(position.x(), y as u8, position.z());
 						
-						blocks.set(position, &foliage);
+						if self.blocks.foliage_replace.matches(blocks.get(position, &palette)) {
+							blocks.set(position, &foliage);
+						}
 					}
 				}
 			}
@@ -71,6 +109,23 @@ impl<B> Decorator<B> for TreeDecorator<B> where B: Target {
 	}
 }
 
+impl TreeDecorator<u16> {
+	/// Birch trees, which have the same shape as oak trees but are 1 block taller.
+	pub fn birch() -> Self {
+		TreeDecorator {
+			blocks: TreeBlocks {
+				log:     17*16 + 2,
+				foliage: 18*16 + 2,
+				.. TreeBlocks::default()
+			},
+			settings: TreeSettings {
+				min_trunk_height: 5,
+				.. TreeSettings::default()
+			}
+		}
+	}
+}
+
 impl Default for TreeDecorator<u16> {
 	fn default() -> Self {
 		TreeDecorator {
@@ -80,12 +135,67 @@ impl Default for TreeDecorator<u16> {
 	}
 }
 
-struct TreeBlocks<B> where B: Target {
-	log:      B,
-	foliage:  B,
-	replace:  BlockMatcher<B>,
-	soil:     BlockMatcher<B>,
-	new_soil: B
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TreeBlocks<B> where B: Target {
+	pub log:      B,
+	pub foliage:  B,
+	/// Blocks that the trunk can replace. The space around the tree needs to consist of these blocks for it to generate.
+	pub replace:  BlockMatcher<B>,
+	/// Blocks that foliage can replace. Like the Notchian generator, this defaults to every block that is not an
+	/// opaque cube. Tree factories fill it in from the block registry when a config leaves it out.
+	#[serde(default="BlockMatcher::none")]
+	pub foliage_replace: BlockMatcher<B>,
+	/// Blocks that the tree can grow on.
+	pub soil:     BlockMatcher<B>,
+	/// Replaces the soil below the trunk, such as grass turning into dirt.
+	pub new_soil: B
+}
+
+impl<B> TreeBlocks<B> where B: Target {
+	/// Checks that every block around the tree that is within the bounding radius returned for each Y level can be
+	/// replaced by the trunk. The checked area extends up to 1 block above the tree.
+	pub fn is_clear<F>(&self, quad: &mut Quad<B>, orgin: QuadPosition, height: u32, bounding_radius: F) -> bool where F: Fn(u32) -> u32 {
+		let base = orgin.y() as u32;
+
+		for y in base..(base + height + 2) {
+			if y >= HEIGHT_LIMIT {
+				return false;
+			}
+
+			let radius = bounding_radius(y) as i8;
+
+			for x_offset in -radius..(radius + 1) {
+				for z_offset in -radius..(radius + 1) {
+					let position = match orgin.offset((x_offset, 0, z_offset)) {
+						Some(position) => QuadPosition::new(position.x(), y as u8, position.z()),
+						None => return false
+					};
+
+					if !self.replace.matches(quad.get(position)) {
+						return false;
+					}
+				}
+			}
+		}
+
+		true
+	}
+
+	/// Replaces the soil below the tree, returning false without changing anything if the tree cannot grow there.
+	pub fn place_soil(&self, quad: &mut Quad<B>, orgin: QuadPosition) -> bool {
+		let below = match orgin.offset(dir::Down) {
+			Some(below) => below,
+			None => return false
+		};
+
+		if !self.soil.matches(quad.get(below)) {
+			return false;
+		}
+
+		quad.set_immediate(below, &self.new_soil);
+
+		true
+	}
 }
 
 impl Default for TreeBlocks<u16> {
@@ -94,20 +204,25 @@ impl Default for TreeBlocks<u16> {
 			log:      17*16,
 			foliage:  18*16,
 			replace:  BlockMatcher::include([0*16, 18*16].iter()),
+			foliage_replace: BlockMatcher {
+				blocks: NOT_OPAQUE_CUBES.iter().flat_map(|&id| (0..16).map(move |meta| id * 16 + meta)).collect(),
+				blacklist: false
+			},
 			soil:     BlockMatcher::include([2*16, 3*16].iter()),
 			new_soil: 3*16
 		}
 	}
 }
 
-struct TreeSettings {
-	min_trunk_height: u32,
-	add_trunk_height: u32,
-	foliage_layers_on_trunk: u32,
-	foliage_layers_off_trunk: u32,
-	foliage_slope: u32,
-	foliage_radius_base: u32,
-	foliage_corner_chance: u32
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TreeSettings {
+	pub min_trunk_height: u32,
+	pub add_trunk_height: u32,
+	pub foliage_layers_on_trunk: u32,
+	pub foliage_layers_off_trunk: u32,
+	pub foliage_slope: u32,
+	pub foliage_radius_base: u32,
+	pub foliage_corner_chance: u32
 }
 
 impl TreeSettings {
@@ -163,17 +278,39 @@ struct Tree {
 impl Tree {
 	/// Radius of the foliage at a given location. 0 is just the trunk.
 	fn foliage_radius(&self, y: u32) -> u32 {
-		(self.leaves_radius_base + self.trunk_top - y) / self.leaves_slope
+		self.leaves_radius_base + (self.trunk_top - y) / self.leaves_slope
 	}
 	
 	/// Radius of the bounding box for the foliage at a given level. 0 for just checking the trunk.
 	fn bounding_radius(&self, y: u32) -> u32 {
 		if y == (self.orgin.y() as u32) {
 			0
-		} else if y > self.trunk_top {
+		} else if y + 1 >= self.trunk_top {
 			2
 		} else {
 			1
 		}
 	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::TreeBlocks;
+	use config::blocks::BlocksConfig;
+	use serde_json;
+
+	#[test]
+	fn test_default_foliage_replace() {
+		let blocks = serde_json::from_str::<BlocksConfig>(include_str!("../../profiles/b173/blocks.json")).unwrap().to_registry().unwrap();
+		let not_opaque = blocks.not_opaque();
+		let foliage_replace = TreeBlocks::default().foliage_replace;
+
+		for block in 0..4096u16 {
+			assert_eq!(foliage_replace.matches(&block), not_opaque.matches(&block), "block {}:{}", block / 16, block % 16);
+		}
+
+		assert!(foliage_replace.matches(&(18*16 + 2)));
+		assert!(!foliage_replace.matches(&(17*16)));
+		assert!(!foliage_replace.matches(&(3*16)));
+	}
 }
\ No newline at end of file
@@ -1,8 +1,8 @@
 use vocs::indexed::Target;
 use matcher::BlockMatcher;
 use vocs::position::{QuadPosition, Offset};
-use vocs::view::QuadMut;
-use super::{Decorator, DecoratorFactory, Result};
+use decorator::quad::Quad;
+use super::{Decorator, DecoratorFactory, Context, Result};
 use java_rand::Random;
 use trig;
 use serde_json;
@@ -39,12 +39,12 @@ pub struct SeasideVeinDecorator<B> where B: Target {
 }
 
 impl<B> Decorator<B> for SeasideVeinDecorator<B> where B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, rng: &mut Random, position: QuadPosition) -> Result {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, context: &Context<B>) -> Result {
 		if !self.ocean.matches(quad.get(position.offset((-8, 0, -8)).unwrap())) {
 			return Ok(());
 		}
 
-		self.vein.generate(quad, rng, position)
+		self.vein.generate(quad, rng, position, context)
 	}
 }
 
@@ -55,7 +55,7 @@ pub struct VeinDecorator<B> where B: Target {
 }
 
 impl<B> Decorator<B> for VeinDecorator<B> where B: Target {
-	fn generate(&self, quad: &mut QuadMut<B>, rng: &mut Random, position: QuadPosition) -> Result {
+	fn generate(&self, quad: &mut Quad<B>, rng: &mut Random, position: QuadPosition, context: &Context<B>) -> Result {
 		let vein = Vein::create(self.size, (position.x() as i32, position.y() as i32, position.z() as i32), rng);
 		self.blocks.generate(&vein, quad, rng)
 	}
@@ -68,28 +68,51 @@ pub struct VeinBlocks<B> where B: Target {
 }
 
 impl<B> VeinBlocks<B> where B: Target {
-	pub fn generate(&self, vein: &Vein, quad: &mut QuadMut<B>, rng: &mut Random) -> Result {
+	pub fn generate(&self, vein: &Vein, quad: &mut Quad<B>, rng: &mut Random) -> Result {
 		quad.ensure_available(self.block.clone());
 		
-		let (mut blocks, palette) = quad.freeze_palette();
-		
-		let block = palette.reverse_lookup(&self.block).unwrap();
-		
-		for index in 0..(vein.size+1) {
-			let blob = vein.blob(index, rng);
+		// Parts of the vein outside of the quad on the X or Z axis are spilled once the frozen palette is released.
+		let mut outside = Vec::new();
+
+		{
+			let (mut blocks, palette) = quad.freeze_palette();
+			
+			let block = palette.reverse_lookup(&self.block).unwrap();
 			
-			for y in blob.lower.1..(blob.upper.1 + 1) {
-				for z in blob.lower.2..(blob.upper.2 + 1) {
-					for x in blob.lower.2..(blob.upper.2 + 1) {
-						let at = QuadPosition::new(x as u8, y as u8, z as u8); // TODO
+			for index in 0..(vein.size+1) {
+				let blob = vein.blob(index, rng);
+				
+				for y in blob.lower.1..(blob.upper.1 + 1) {
+					for z in blob.lower.2..(blob.upper.2 + 1) {
+						for x in blob.lower.0..(blob.upper.0 + 1) {
+							if blob.distance_squared((x, y, z)) >= 1.0 {
+								continue;
+							}
+
+							// Parts of the vein below or above the world are discarded, like the Notchian generator.
+							if y < 0 || y >= 256 {
+								continue;
+							}
+
+							if x < 0 || x >= 32 || z < 0 || z >= 32 {
+								outside.push((x, y, z));
+								continue;
+							}
+
+							let at = QuadPosition::new(x as u8, y as u8, z as u8);
 
-						if blob.distance_squared((x, y, z)) < 1.0 && self.replace.matches(blocks.get(at, &palette)) {
-							blocks.set(at, &block);
+							if self.replace.matches(blocks.get(at, &palette)) {
+								blocks.set(at, &block);
+							}
 						}
 					}
 				}
 			}
 		}
+
+		for position in outside {
+			quad.set_or_spill(position, &self.block, &self.replace);
+		}
 		
 		Ok(())
 	}
@@ -187,4 +210,54 @@ impl Blob {
 /// Used instead of standard lerp() to preserve operation order.
 fn lerp_fraction(index: f64, size: f64, a: f64, b: f64) -> f64 {
 	a + (b - a) * index / size
+}
+
+#[cfg(test)]
+mod test {
+	use super::{VeinDecorator, VeinBlocks};
+	use decorator::{Decorator, Context};
+	use decorator::quad::Quad;
+	use matcher::BlockMatcher;
+	use java_rand::Random;
+	use vocs::position::{GlobalColumnPosition, QuadPosition};
+
+	#[test]
+	fn test_vein_spills_outside_quad() {
+		let mut world = ::test::quad_world(1*16, 128);
+		let biomes = ::test::plains();
+		let context = Context::new(GlobalColumnPosition::new(0, 0), &biomes);
+		let mut quad = Quad::new(world.get_quad_mut(GlobalColumnPosition::new(0, 0)).unwrap());
+
+		let decorator = VeinDecorator {
+			blocks: VeinBlocks { replace: BlockMatcher::is(1*16), block: 16*16 },
+			size: 32
+		};
+
+		// A vein centered on the corner of the quad always extends into the columns on the -X side.
+		decorator.generate(&mut quad, &mut Random::new(1), QuadPosition::new(0, 64, 0), &context).unwrap();
+
+		assert!(!quad.spilled().is_empty());
+
+		for spilled in quad.spilled() {
+			let (x, _, z) = spilled.position;
+
+			assert!(x < 0 || z < 0, "block inside of the quad was spilled: {:?}", spilled.position);
+			assert_eq!(spilled.block, 16*16);
+			assert!(spilled.replace.matches(&(1*16)));
+		}
+
+		let mut placed = 0;
+
+		for x in 0..8 {
+			for y in 60..76 {
+				for z in 0..8 {
+					if *quad.get(QuadPosition::new(x, y, z)) == 16*16 {
+						placed += 1;
+					}
+				}
+			}
+		}
+
+		assert!(placed > 0);
+	}
 }
\ No newline at end of file
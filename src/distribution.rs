@@ -19,7 +19,7 @@ pub enum ChanceOrdering {
 	CheckChanceBeforePayload
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chance<D> where D: Distribution {
 	/// Chance for this distribution to return its value instead of 0.
 	/// Represented as probability = 1 / chance.
@@ -59,13 +59,14 @@ impl<D> Distribution for Chance<D> where D: Distribution {
 }
 
 /// Baseline distribution. This should be general enough to fit most use cases.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "kind")]
 pub enum Baseline {
 	Constant { value: u32 },
 	Linear(Linear),
 	Packed2(Packed2),
 	Packed3(Packed3),
+	Packed3Offset(Packed3Offset),
 	Centered(Centered)
 }
 
@@ -76,6 +77,7 @@ impl Distribution for Baseline {
 			Baseline::Linear(ref linear) => linear.next(rng),
 			Baseline::Packed2(ref packed2) => packed2.next(rng),
 			Baseline::Packed3(ref packed3) => packed3.next(rng),
+			Baseline::Packed3Offset(ref packed3) => packed3.next(rng),
 			Baseline::Centered(ref centered) => centered.next(rng)
 		}
 	}
@@ -88,7 +90,7 @@ impl Distribution for u32 {
 }
 
 /// Plain old linear distribution, with a minimum and maximum.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Linear {
 	pub min: u32,
 	pub max: u32
@@ -101,7 +103,7 @@ impl Distribution for Linear {
 }
 
 /// Distribution that packs more values to the minimum value. This is based on 2 RNG iterations.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Packed2 {
 	pub min: u32,
 	/// Minimum height passed to the second RNG call (the linear call).
@@ -119,7 +121,7 @@ impl Distribution for Packed2 {
 
 /// Distribution that packs more values to the minimum value. This is based on 3 RNG iterations, and is more extreme.
 /// The average is around `(max+1)/8 - 1`, a simplified form of `(max+1)/2³ - 1`.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Packed3 {
 	pub max: u32
 }
@@ -132,8 +134,24 @@ impl Distribution for Packed3 {
 	}
 }
 
+/// Like Packed3, but with an offset added to the bound of the inner RNG calls, so that they can never return 0.
+/// This is the distribution of lava springs: `rng(rng(rng(bound) + offset) + offset)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Packed3Offset {
+	pub bound: u32,
+	pub offset: u32
+}
+
+impl Distribution for Packed3Offset {
+	fn next(&self, rng: &mut Random) -> u32 {
+		let result = rng.next_u32_bound(self.bound);
+		let result = rng.next_u32_bound(result + self.offset);
+		rng.next_u32_bound(result + self.offset)
+	}
+}
+
 /// Distribution centered around a certain point, with a maximum variance.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Centered {
 	pub center: u32,
 	pub radius: u32
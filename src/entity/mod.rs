@@ -0,0 +1,48 @@
+//! Things generated alongside the blocks of a chunk, stored in the NBT of the chunk.
+
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeStruct;
+
+pub mod tile;
+pub mod tick;
+
+/// An entity, such as a mob or a minecart. Only the tags shared by every entity are stored, entity specific tags
+/// are left to their defaults when the chunk is loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+	/// Entity ID, like `Skeleton` or `Minecart`.
+	pub id: String,
+	pub position: (f64, f64, f64),
+	pub motion: (f64, f64, f64),
+	/// Yaw and pitch, in degrees.
+	pub rotation: (f32, f32)
+}
+
+impl Entity {
+	/// Creates an entity standing still at the position.
+	pub fn new(id: String, position: (f64, f64, f64)) -> Self {
+		Entity {
+			id,
+			position,
+			motion: (0.0, 0.0, 0.0),
+			rotation: (0.0, 0.0)
+		}
+	}
+}
+
+impl Serialize for Entity {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		let mut nbt = serializer.serialize_struct("Entity", 8)?;
+
+		nbt.serialize_field("id", &self.id)?;
+		nbt.serialize_field("Pos", &[self.position.0, self.position.1, self.position.2])?;
+		nbt.serialize_field("Motion", &[self.motion.0, self.motion.1, self.motion.2])?;
+		nbt.serialize_field("Rotation", &[self.rotation.0, self.rotation.1])?;
+		nbt.serialize_field("FallDistance", &0.0f32)?;
+		nbt.serialize_field("Fire", &-1i16)?;
+		nbt.serialize_field("Air", &300i16)?;
+		nbt.serialize_field("OnGround", &false)?;
+
+		nbt.end()
+	}
+}
@@ -0,0 +1,39 @@
+/// A block update scheduled to happen some time after the chunk is loaded, such as a liquid starting to flow.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TileTick<B> {
+	/// The block that the update is for. The update is skipped if the block has changed by the time it happens.
+	pub block: B,
+	/// Number of ticks until the update happens.
+	pub delay: i32
+}
+
+/// A scheduled update at an absolute block position.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PlacedTileTick<B> {
+	pub x: i32,
+	pub y: i32,
+	pub z: i32,
+	pub tick: TileTick<B>
+}
+
+impl PlacedTileTick<u16> {
+	/// Returns the form stored in the `TileTicks` list of a chunk, which only has the numeric block ID.
+	pub fn to_nbt(&self) -> TileTickNbt {
+		TileTickNbt {
+			x: self.x,
+			y: self.y,
+			z: self.z,
+			id: (self.tick.block >> 4) as i32,
+			delay: self.tick.delay
+		}
+	}
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+pub struct TileTickNbt {
+	pub x: i32,
+	pub y: i32,
+	pub z: i32,
+	#[serde(rename="i")] pub id:    i32,
+	#[serde(rename="t")] pub delay: i32
+}
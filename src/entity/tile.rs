@@ -0,0 +1,76 @@
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeStruct;
+
+/// A stack of items, as stored in the inventory of a container.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+pub struct ItemStack {
+	#[serde(rename="id")]     pub id:     i16,
+	#[serde(rename="Damage")] pub damage: i16,
+	#[serde(rename="Count")]  pub count:  i8
+}
+
+/// Data attached to a single block, such as the contents of a chest.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TileEntity {
+	/// A chest with the item stacks in each of its 27 slots. Slots without a stack are left out.
+	Chest { items: Vec<(u8, ItemStack)> },
+	/// A mob spawner that spawns the entity with the given ID, after waiting `delay` ticks.
+	MobSpawner { entity: String, delay: i16 }
+}
+
+impl TileEntity {
+	/// ID used in the NBT of the tile entity, as of Beta 1.7.3.
+	pub fn id(&self) -> &'static str {
+		match *self {
+			TileEntity::Chest { .. }      => "Chest",
+			TileEntity::MobSpawner { .. } => "MobSpawner"
+		}
+	}
+}
+
+/// A tile entity at an absolute block position, in the format stored in the `TileEntities` list of a chunk.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PlacedTileEntity {
+	pub x: i32,
+	pub y: i32,
+	pub z: i32,
+	pub tile_entity: TileEntity
+}
+
+#[derive(Serialize)]
+struct Slot {
+	#[serde(rename="Slot")]   slot:   i8,
+	#[serde(rename="id")]     id:     i16,
+	#[serde(rename="Damage")] damage: i16,
+	#[serde(rename="Count")]  count:  i8
+}
+
+impl Serialize for PlacedTileEntity {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		let mut nbt = serializer.serialize_struct("TileEntity", 6)?;
+
+		nbt.serialize_field("id", self.tile_entity.id())?;
+		nbt.serialize_field("x", &self.x)?;
+		nbt.serialize_field("y", &self.y)?;
+		nbt.serialize_field("z", &self.z)?;
+
+		match self.tile_entity {
+			TileEntity::Chest { ref items } => {
+				let slots = items.iter().map(|&(slot, stack)| Slot {
+					slot: slot as i8,
+					id: stack.id,
+					damage: stack.damage,
+					count: stack.count
+				}).collect::<Vec<_>>();
+
+				nbt.serialize_field("Items", &slots)?;
+			},
+			TileEntity::MobSpawner { ref entity, delay } => {
+				nbt.serialize_field("EntityId", entity)?;
+				nbt.serialize_field("Delay", &delay)?;
+			}
+		}
+
+		nbt.end()
+	}
+}
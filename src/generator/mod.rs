@@ -6,6 +6,8 @@ pub mod overworld_173;
 pub mod nether_173;
 pub mod sky_173;
 
-pub trait Pass<B> where B: Target {
+/// A pass that is applied to a single column at a time. Passes must not depend on neighboring columns,
+/// which allows columns to be generated in parallel.
+pub trait Pass<B>: Sync where B: Target {
 	fn apply(&self, target: &mut ColumnMut<B>, chunk: GlobalColumnPosition);
 }
\ No newline at end of file
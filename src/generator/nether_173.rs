@@ -56,7 +56,7 @@ pub struct ShapePass<B> where B: Target {
 	sea_coord: u8
 }
 
-impl<B> Pass<B> for ShapePass<B> where B: Target {
+impl<B> Pass<B> for ShapePass<B> where B: Target + Sync {
 	fn apply(&self, target: &mut ColumnMut<B>, chunk: GlobalColumnPosition) {
 		let offset = Vector2::new(
 			(chunk.x() as f64) * 4.0,
@@ -2,7 +2,7 @@ use java_rand::Random;
 use noise::octaves::PerlinOctaves;
 use biome::climate::{ClimateSettings, ClimateSource};
 use biome::source::BiomeSource;
-use biome::{Lookup, Surface};
+use biome::Surface;
 use noise_field::height::{HeightSettings, HeightSource};
 use noise_field::volume::{TriNoiseSettings, TriNoiseSource, FieldSettings, trilinear128};
 use generator::Pass;
@@ -42,7 +42,9 @@ impl Default for Settings<u16> {
 	}
 }
 
-pub fn passes<B>(seed: u64, settings: Settings<B>, biome_lookup: Lookup<B>) -> (ShapePass<B>, PaintPass<B>) where B: Target {
+/// Creates the shape and paint passes. The paint pass uses `biomes` directly, and the shape pass uses its climate,
+/// so it should be created from the same seed and `settings.climate`.
+pub fn passes<B>(seed: u64, settings: Settings<B>, biomes: BiomeSource<B>) -> (ShapePass<B>, PaintPass<B>) where B: Target {
 	let mut rng = Random::new(seed);
 	
 	let tri = TriNoiseSource::new(&mut rng, &settings.tri);
@@ -57,7 +59,7 @@ pub fn passes<B>(seed: u64, settings: Settings<B>, biome_lookup: Lookup<B>) -> (
 	
 	let height  = HeightSource::new(&mut rng, &settings.height);
 	let field   = settings.field;
-	let climate = ClimateSource::new(seed, settings.climate);
+	let climate = biomes.climate().clone();
 
 	(
 		ShapePass { 
@@ -69,7 +71,7 @@ pub fn passes<B>(seed: u64, settings: Settings<B>, biome_lookup: Lookup<B>) -> (
 			sea_coord: settings.sea_coord 
 		},
 		PaintPass {
-			biomes: BiomeSource::new(ClimateSource::new(seed, settings.climate), biome_lookup),
+			biomes,
 			blocks: settings.paint_blocks, 
 			sand, 
 			gravel, 
@@ -81,6 +83,21 @@ pub fn passes<B>(seed: u64, settings: Settings<B>, biome_lookup: Lookup<B>) -> (
 	)
 }
 
+/// Creates the noise that the Notchian generator uses to vary the amount of trees in each chunk, confusingly named
+/// the "mob spawner noise". It is the last noise created from the world seed, so the terrain noises created by
+/// `passes` have to be skipped first.
+pub fn tree_noise(seed: u64) -> PerlinOctaves {
+	let mut rng = Random::new(seed);
+	
+	// Lower, upper, and main noise, followed by the gravel, thickness, biome influence, and depth noise.
+	// The sand noise reuses the RNG state of the gravel noise, so it is not skipped.
+	for &octaves in &[16, 16, 8, 4, 4, 10, 16] {
+		PerlinOctaves::new(&mut rng, octaves, Vector3::new(1.0, 1.0, 1.0));
+	}
+	
+	PerlinOctaves::new(&mut rng, 8, Vector3::new(0.5, 0.5, 1.0))
+}
+
 pub struct ShapeBlocks<B> where B: Target {
 	pub solid: B,
 	pub ocean: B,
@@ -108,7 +125,7 @@ pub struct ShapePass<B> where B: Target {
 	sea_coord: u8
 }
 
-impl<B> Pass<B> for ShapePass<B> where B: Target {
+impl<B> Pass<B> for ShapePass<B> where B: Target + Sync {
 	fn apply(&self, target: &mut ColumnMut<B>, chunk: GlobalColumnPosition) {
 		let offset = Point2::new(
 			(chunk.x() as f64) * 4.0,
@@ -325,7 +342,7 @@ impl<B> PaintPass<B> where B: Target {
 	}
 }
 
-impl<B> Pass<B> for PaintPass<B> where B: Target {
+impl<B> Pass<B> for PaintPass<B> where B: Target + Sync {
 	fn apply(&self, target: &mut ColumnMut<B>, chunk: GlobalColumnPosition) {
 		let block = ((chunk.x() * 16) as f64, (chunk.z() * 16) as f64);
 		let seed = (chunk.x() as i64).wrapping_mul(341873128712).wrapping_add((chunk.z() as i64).wrapping_mul(132897987541));
@@ -48,7 +48,7 @@ pub struct ShapePass<B> where B: Target {
 	tri:     TriNoiseSource
 }
 
-impl<B> Pass<B> for ShapePass<B> where B: Target {
+impl<B> Pass<B> for ShapePass<B> where B: Target + Sync {
 	fn apply(&self, target: &mut ColumnMut<B>, chunk: GlobalColumnPosition) {
 		let offset = Vector2::new(
 			(chunk.x() as f64) * 2.0,
@@ -0,0 +1,118 @@
+//! Numeric IDs of the items that exist in Beta 1.7.3, and their namespaced names.
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{self, Visitor};
+use std::fmt;
+
+/// Items by namespaced name, using the names from before the 1.13 flattening so that the damage value still selects
+/// between item variants, like the colors of dye.
+const ITEMS: &'static [(&'static str, i16)] = &[
+	("iron_shovel", 256),        ("iron_pickaxe", 257),      ("iron_axe", 258),           ("flint_and_steel", 259),
+	("apple", 260),              ("bow", 261),               ("arrow", 262),              ("coal", 263),
+	("diamond", 264),            ("iron_ingot", 265),        ("gold_ingot", 266),         ("iron_sword", 267),
+	("wooden_sword", 268),       ("wooden_shovel", 269),     ("wooden_pickaxe", 270),     ("wooden_axe", 271),
+	("stone_sword", 272),        ("stone_shovel", 273),      ("stone_pickaxe", 274),      ("stone_axe", 275),
+	("diamond_sword", 276),      ("diamond_shovel", 277),    ("diamond_pickaxe", 278),    ("diamond_axe", 279),
+	("stick", 280),              ("bowl", 281),              ("mushroom_stew", 282),      ("golden_sword", 283),
+	("golden_shovel", 284),      ("golden_pickaxe", 285),    ("golden_axe", 286),         ("string", 287),
+	("feather", 288),            ("gunpowder", 289),         ("wooden_hoe", 290),         ("stone_hoe", 291),
+	("iron_hoe", 292),           ("diamond_hoe", 293),       ("golden_hoe", 294),         ("wheat_seeds", 295),
+	("wheat", 296),              ("bread", 297),             ("leather_helmet", 298),     ("leather_chestplate", 299),
+	("leather_leggings", 300),   ("leather_boots", 301),     ("chainmail_helmet", 302),   ("chainmail_chestplate", 303),
+	("chainmail_leggings", 304), ("chainmail_boots", 305),   ("iron_helmet", 306),        ("iron_chestplate", 307),
+	("iron_leggings", 308),      ("iron_boots", 309),        ("diamond_helmet", 310),     ("diamond_chestplate", 311),
+	("diamond_leggings", 312),   ("diamond_boots", 313),     ("golden_helmet", 314),      ("golden_chestplate", 315),
+	("golden_leggings", 316),    ("golden_boots", 317),      ("flint", 318),              ("porkchop", 319),
+	("cooked_porkchop", 320),    ("painting", 321),          ("golden_apple", 322),       ("sign", 323),
+	("wooden_door", 324),        ("bucket", 325),            ("water_bucket", 326),       ("lava_bucket", 327),
+	("minecart", 328),           ("saddle", 329),            ("iron_door", 330),          ("redstone", 331),
+	("snowball", 332),           ("boat", 333),              ("leather", 334),            ("milk_bucket", 335),
+	("brick", 336),              ("clay_ball", 337),         ("reeds", 338),              ("paper", 339),
+	("book", 340),               ("slime_ball", 341),        ("chest_minecart", 342),     ("furnace_minecart", 343),
+	("egg", 344),                ("compass", 345),           ("fishing_rod", 346),        ("clock", 347),
+	("glowstone_dust", 348),     ("fish", 349),              ("cooked_fish", 350),        ("dye", 351),
+	("bone", 352),               ("sugar", 353),             ("cake", 354),               ("bed", 355),
+	("repeater", 356),           ("cookie", 357),            ("filled_map", 358),         ("shears", 359),
+	("record_13", 2256),         ("record_cat", 2257)
+];
+
+/// Looks up the numeric ID of an item by its name, with or without the `minecraft:` namespace.
+pub fn id_of(name: &str) -> Option<i16> {
+	let name = if name.starts_with("minecraft:") { &name[10..] } else { name };
+
+	ITEMS.iter().find(|&&(item, _)| item == name).map(|&(_, id)| id)
+}
+
+/// The numeric ID of an item. In configs, this is either the number itself, or a namespaced name like
+/// `"minecraft:iron_ingot"` that is resolved when the config is loaded.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ItemId(pub i16);
+
+impl Serialize for ItemId {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		serializer.serialize_i16(self.0)
+	}
+}
+
+impl Deserialize for ItemId {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer {
+		deserializer.deserialize(ItemIdVisitor)
+	}
+}
+
+struct ItemIdVisitor;
+
+impl Visitor for ItemIdVisitor {
+	type Value = ItemId;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a numeric item ID or an item name")
+	}
+
+	fn visit_u64<E>(self, value: u64) -> Result<ItemId, E> where E: de::Error {
+		if value > i16::max_value() as u64 {
+			return Err(E::custom(format!("item ID {} is out of range", value)));
+		}
+
+		Ok(ItemId(value as i16))
+	}
+
+	fn visit_i64<E>(self, value: i64) -> Result<ItemId, E> where E: de::Error {
+		if value < 0 || value > i16::max_value() as i64 {
+			return Err(E::custom(format!("item ID {} is out of range", value)));
+		}
+
+		Ok(ItemId(value as i16))
+	}
+
+	fn visit_str<E>(self, value: &str) -> Result<ItemId, E> where E: de::Error {
+		// Numeric strings follow the same rules as numbers.
+		match value.parse::<i64>() {
+			Ok(id) => self.visit_i64(id),
+			Err(_) => id_of(value).map(ItemId).ok_or_else(|| E::custom(format!("unknown item: {}", value)))
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{id_of, ItemId};
+	use serde_json;
+
+	#[test]
+	fn test_item_ids() {
+		assert_eq!(id_of("minecraft:saddle"), Some(329));
+		assert_eq!(id_of("dye"), Some(351));
+		assert_eq!(id_of("minecraft:record_cat"), Some(2257));
+		assert_eq!(id_of("minecraft:stone"), None);
+
+		assert_eq!(serde_json::from_str::<ItemId>("265").unwrap(), ItemId(265));
+		assert_eq!(serde_json::from_str::<ItemId>("\"minecraft:iron_ingot\"").unwrap(), ItemId(265));
+		assert!(serde_json::from_str::<ItemId>("\"minecraft:nonexistent\"").is_err());
+
+		assert_eq!(serde_json::from_str::<ItemId>("\"265\"").unwrap(), ItemId(265));
+		assert!(serde_json::from_str::<ItemId>("-5").is_err());
+		assert!(serde_json::from_str::<ItemId>("\"-5\"").is_err());
+		assert!(serde_json::from_str::<ItemId>("\"40000\"").is_err());
+	}
+}
@@ -9,8 +9,10 @@ extern crate nbt_serde;
 extern crate byteorder;
 extern crate bit_vec;
 extern crate vocs;
+extern crate rs25;
 extern crate cgmath;
 extern crate java_rand;
+extern crate rayon;
 
 pub mod noise;
 pub mod biome;
@@ -24,11 +26,24 @@ pub mod distribution;
 pub mod segmented;
 pub mod config;
 pub mod matcher;
+pub mod block;
+pub mod item;
+pub mod world;
+pub mod lighting;
+pub mod output;
+pub mod entity;
 
 #[cfg(test)]
 mod test {
 	use std::fs::File;
 	use std::io::Read;
+	use std::borrow::Cow;
+	use vocs::indexed::ChunkIndexed;
+	use vocs::world::world::World;
+	use vocs::position::{GlobalColumnPosition, GlobalChunkPosition, ChunkPosition};
+	use biome::{Biome, Surface, Lookup};
+	use biome::source::BiomeSource;
+	use biome::climate::{ClimateSource, ClimateSettings};
 
 	pub fn read_u64s(name: &str) -> Vec<u64> {
 		let file = File::open(format!("test_data/{}.txt", name)).unwrap();
@@ -70,4 +85,45 @@ mod test {
 	pub fn read_f64s(name: &str) -> Vec<f64> {
 		read_u64s(name).iter().map(|&v| f64::from_bits(v)).collect::<Vec<_>>()
 	}
+
+	/// Creates a world containing the 2x2 quad of columns at the origin, which is air except for `ground` below `height`.
+	pub fn quad_world(ground: u16, height: u8) -> World<ChunkIndexed<u16>> {
+		let mut world = World::new();
+
+		for &(x, z) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+			let column = GlobalColumnPosition::new(x, z);
+
+			world.set_column(column, [
+				ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0),
+				ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0),
+				ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0),
+				ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0)
+			]);
+
+			for y in 0..height {
+				let chunk = world.get_mut(GlobalChunkPosition::from_column(column, y >> 4)).unwrap();
+
+				chunk.ensure_available(ground);
+
+				for x in 0..16 {
+					for z in 0..16 {
+						chunk.set_immediate(ChunkPosition::new(x, y & 15, z), &ground);
+					}
+				}
+			}
+		}
+
+		world
+	}
+
+	/// Creates a biome source where every position is in the same grassy biome.
+	pub fn plains() -> BiomeSource<u16> {
+		let biome = Biome {
+			surface: Surface { top: 2*16, fill: 3*16, chain: vec![] },
+			name: Cow::Borrowed("Plains"),
+			id: 1
+		};
+
+		BiomeSource::new(ClimateSource::new(0, ClimateSettings::default()), Lookup::filled(&biome))
+	}
 }
\ No newline at end of file
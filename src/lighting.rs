@@ -0,0 +1,267 @@
+//! Sky light, block light, and heightmap computation for generated columns.
+
+use vocs::indexed::ChunkIndexed;
+use vocs::world::world::World;
+use vocs::world::shared::{NoPack, SharedWorld};
+use vocs::position::{GlobalColumnPosition, GlobalChunkPosition, Offset, dir};
+use vocs::nibbles::{u4, ChunkNibbles, BulkNibbles};
+use vocs::mask::{ChunkMask, LayerMask};
+use vocs::sparse::SparseStorage;
+use vocs::component::*;
+use vocs::view::{SplitDirectional, Directional};
+
+use rs25::dynamics::light::{SkyLightSources, BlockLightSources, Lighting, HeightMapBuilder};
+use rs25::dynamics::queue::Queue;
+
+use block::BlockRegistry;
+
+use std::collections::HashMap;
+
+/// Light data for an area of columns, see `light_area`.
+pub struct AreaLight {
+	pub sky_light: SharedWorld<NoPack<ChunkNibbles>>,
+	pub block_light: SharedWorld<NoPack<ChunkNibbles>>,
+	/// Heightmaps of each column, indexed by the (X, Z) coordinates of the column.
+	pub heightmaps: HashMap<(i32, i32), Vec<u32>> // TODO: Better vocs integration.
+}
+
+impl AreaLight {
+	/// Removes the sky light of a chunk, returning fully dark light data if the chunk was never lit.
+	pub fn take_sky_light(&mut self, position: GlobalChunkPosition) -> ChunkNibbles {
+		self.sky_light.remove(position).map(|light| light.0).unwrap_or_else(ChunkNibbles::default)
+	}
+
+	/// Removes the block light of a chunk, returning fully dark light data if no light reached the chunk.
+	pub fn take_block_light(&mut self, position: GlobalChunkPosition) -> ChunkNibbles {
+		self.block_light.remove(position).map(|light| light.0).unwrap_or_else(ChunkNibbles::default)
+	}
+
+	pub fn take_heightmap(&mut self, position: GlobalColumnPosition) -> Option<Vec<u32>> {
+		self.heightmaps.remove(&(position.x(), position.z()))
+	}
+}
+
+/// Computes the sky light, block light, and heightmaps of the columns from `lower` (inclusive) to `upper` (exclusive).
+///
+/// Light freely crosses chunk, sector, and region boundaries. Light can travel at most 15 blocks, so the light of a
+/// column only depends on itself and its direct neighbors. Therefore, the area is lit together with a 1 column
+/// border, which is used as a light source and then discarded. The blocks of the area and the border must be final.
+/// Columns of the border that are not present in the world are treated as if they were completely dark.
+pub fn light_area(world: &World<ChunkIndexed<u16>>, blocks: &BlockRegistry<u16>, lower: GlobalColumnPosition, upper: GlobalColumnPosition) -> AreaLight {
+	let opacity_info = blocks.opacity();
+	let emission_info = blocks.emission();
+
+	let mut sky_light = SharedWorld::<NoPack<ChunkNibbles>>::new();
+	let mut block_light = SharedWorld::<NoPack<ChunkNibbles>>::new();
+	let mut sky_incomplete = World::<ChunkMask>::new();
+	let mut block_incomplete = World::<ChunkMask>::new();
+	let mut heightmaps = HashMap::<(i32, i32), Vec<u32>>::new();
+
+	let empty_lighting = ChunkNibbles::default();
+
+	let mut queue = Queue::default();
+
+	for x in (lower.x() - 1)..(upper.x() + 1) {
+		for z in (lower.z() - 1)..(upper.z() + 1) {
+			let column_position = GlobalColumnPosition::new(x, z);
+
+			if world.get(GlobalChunkPosition::from_column(column_position, 0)).is_none() {
+				continue;
+			}
+
+			let mut mask = LayerMask::default();
+			let mut heightmap = HeightMapBuilder::new();
+
+			for y in (0..16).rev() {
+				let chunk_position = GlobalChunkPosition::from_column(column_position, y);
+
+				let (blocks, palette) = world.get(chunk_position).unwrap().freeze();
+				let mut opacity = BulkNibbles::new(palette.len());
+				let mut emission = BulkNibbles::new(palette.len());
+				let mut emits = false;
+
+				for (index, value) in palette.iter().enumerate() {
+					opacity.set(index, value.map(|entry| opacity_info.get(entry as usize)).unwrap_or(opacity_info.default_value()));
+
+					let emitted = value.map(|entry| emission_info.get(entry as usize)).unwrap_or(emission_info.default_value());
+					emits |= emitted != u4::new(0);
+					emission.set(index, emitted);
+				}
+
+				let neighbors = || Directional::combine(SplitDirectional {
+					minus_x: &empty_lighting,
+					plus_x: &empty_lighting,
+					minus_z: &empty_lighting,
+					plus_z: &empty_lighting,
+					down: &empty_lighting,
+					up: &empty_lighting
+				});
+
+				// Most chunks do not contain any emitting blocks, so skip them entirely.
+				if emits {
+					let mut light_data = ChunkNibbles::default();
+
+					{
+						let mut light = Lighting::new(&mut light_data, neighbors(), BlockLightSources::new(&emission), opacity.clone());
+
+						light.initial(blocks, &mut queue);
+						light.finish(blocks, &mut queue);
+					}
+
+					spill_out(chunk_position, &mut block_incomplete, queue.reset_spills());
+
+					block_light.set(chunk_position, NoPack(light_data));
+				}
+
+				let sources = SkyLightSources::build(blocks, &opacity, mask);
+
+				let mut light_data = ChunkNibbles::default();
+
+				let sources = {
+					let mut light = Lighting::new(&mut light_data, neighbors(), sources, opacity);
+
+					light.initial(blocks, &mut queue);
+					light.finish(blocks, &mut queue);
+
+					light.decompose().1
+				};
+
+				mask = heightmap.add(sources);
+
+				spill_out(chunk_position, &mut sky_incomplete, queue.reset_spills());
+
+				sky_light.set(chunk_position, NoPack(light_data));
+			}
+
+			heightmaps.insert((x, z), heightmap.build().into_vec());
+		}
+	}
+
+	complete(world, &mut sky_light, sky_incomplete, &mut queue, &opacity_info, &heightmaps, None);
+	complete(world, &mut block_light, block_incomplete, &mut queue, &opacity_info, &heightmaps, Some(&emission_info));
+
+	AreaLight { sky_light, block_light, heightmaps }
+}
+
+/// Each chunk was initially lit as if its neighbors were dark. This feeds the light that spilled over chunk boundaries
+/// back into the neighbors until no light changes anymore. Sky light is completed if `emission_info` is None,
+/// otherwise block light is completed. Only the columns that have a heightmap are lit.
+fn complete(world: &World<ChunkIndexed<u16>>, light_world: &mut SharedWorld<NoPack<ChunkNibbles>>, mut incomplete: World<ChunkMask>, queue: &mut Queue, opacity_info: &SparseStorage<u4>, heightmaps: &HashMap<(i32, i32), Vec<u32>>, emission_info: Option<&SparseStorage<u4>>) {
+	let empty_lighting = ChunkNibbles::default();
+
+	while incomplete.sectors().len() > 0 {
+		let incomplete_front = ::std::mem::replace(&mut incomplete, World::new());
+
+		for (sector_position, mut sector) in incomplete_front.into_sectors() {
+			while let Some((position, incomplete_mask)) = sector.pop_first() {
+				let column_position = GlobalColumnPosition::combine(sector_position, position.layer());
+				let chunk_position = GlobalChunkPosition::from_column(column_position, position.y());
+
+				let heightmap = match heightmaps.get(&(column_position.x(), column_position.z())) {
+					Some(heightmap) => heightmap,
+					None => continue // Outside of the area and its border, no sense in lighting the void.
+				};
+
+				let (blocks, palette) = world.get(chunk_position).unwrap().freeze();
+				let mut opacity = BulkNibbles::new(palette.len());
+
+				for (index, value) in palette.iter().enumerate() {
+					opacity.set(index, value.map(|entry| opacity_info.get(entry as usize)).unwrap_or(opacity_info.default_value()));
+				}
+
+				// Block light is only stored for chunks that have been reached by light.
+				light_world.get_or_create_sector_mut(sector_position);
+
+				let light_sector = light_world.get_sector(sector_position).unwrap();
+				let mut central = light_sector.get_or_create(position);
+
+				// Neighbors may be in a different sector than the central chunk.
+				let neighbor = |position: Option<GlobalChunkPosition>| position.and_then(|position|
+					light_world.get_sector(position.global_sector()).map(|sector| sector[position.local_chunk()].read())
+				);
+
+				let locks = SplitDirectional {
+					up:      neighbor(chunk_position.plus_y()),
+					down:    neighbor(chunk_position.minus_y()),
+					plus_x:  neighbor(chunk_position.plus_x()),
+					minus_x: neighbor(chunk_position.minus_x()),
+					plus_z:  neighbor(chunk_position.plus_z()),
+					minus_z: neighbor(chunk_position.minus_z())
+				};
+
+				let neighbors = Directional::combine(SplitDirectional {
+					up: locks.up.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting),
+					down: locks.down.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting),
+					plus_x: locks.plus_x.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting),
+					minus_x: locks.minus_x.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting),
+					plus_z: locks.plus_z.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting),
+					minus_z: locks.minus_z.as_ref().and_then(|chunk| chunk.as_ref().map(|chunk| &chunk.0)).unwrap_or(&empty_lighting)
+				});
+
+				queue.reset_from_mask(incomplete_mask);
+
+				match emission_info {
+					None => {
+						let sources = SkyLightSources::slice(&heightmap, position.y());
+						let mut light = Lighting::new(&mut central, neighbors, sources, opacity);
+
+						light.finish(blocks, queue);
+					},
+					Some(emission_info) => {
+						let mut emission = BulkNibbles::new(palette.len());
+
+						for (index, value) in palette.iter().enumerate() {
+							emission.set(index, value.map(|entry| emission_info.get(entry as usize)).unwrap_or(emission_info.default_value()));
+						}
+
+						let mut light = Lighting::new(&mut central, neighbors, BlockLightSources::new(&emission), opacity);
+
+						light.finish(blocks, queue);
+					}
+				}
+
+				// Light that reached the edges of this chunk may in turn brighten the neighbors.
+				spill_out(chunk_position, &mut incomplete, queue.reset_spills());
+			}
+		}
+	}
+}
+
+/// Marks the edges of the neighboring chunks that light spilled into, so that they are revisited.
+fn spill_out(chunk_position: GlobalChunkPosition, incomplete: &mut World<ChunkMask>, old_spills: Directional<LayerMask>) {
+	if let Some(up) = chunk_position.plus_y() {
+		if !old_spills[dir::Up].is_filled(false) {
+			incomplete.get_or_create_mut(up).layer_zx_mut(0).combine(&old_spills[dir::Up]);
+		}
+	}
+
+	if let Some(down) = chunk_position.minus_y() {
+		if !old_spills[dir::Down].is_filled(false) {
+			incomplete.get_or_create_mut(down).layer_zx_mut(15).combine(&old_spills[dir::Down]);
+		}
+	}
+
+	if let Some(plus_x) = chunk_position.plus_x() {
+		if !old_spills[dir::PlusX].is_filled(false) {
+			incomplete.get_or_create_mut(plus_x).layer_zy_mut(0).combine(&old_spills[dir::PlusX]);
+		}
+	}
+
+	if let Some(minus_x) = chunk_position.minus_x() {
+		if !old_spills[dir::MinusX].is_filled(false) {
+			incomplete.get_or_create_mut(minus_x).layer_zy_mut(15).combine(&old_spills[dir::MinusX]);
+		}
+	}
+
+	if let Some(plus_z) = chunk_position.plus_z() {
+		if !old_spills[dir::PlusZ].is_filled(false) {
+			incomplete.get_or_create_mut(plus_z).layer_yx_mut(0).combine(&old_spills[dir::PlusZ]);
+		}
+	}
+
+	if let Some(minus_z) = chunk_position.minus_z() {
+		if !old_spills[dir::MinusZ].is_filled(false) {
+			incomplete.get_or_create_mut(minus_z).layer_yx_mut(15).combine(&old_spills[dir::MinusZ]);
+		}
+	}
+}
\ No newline at end of file
@@ -5,6 +5,7 @@ pub mod octaves;
 use java_rand::Random;
 use cgmath::Vector3;
 
+#[derive(Clone)]
 pub struct Permutations {
 	offset: Vector3<f64>,
 	permutations: [u8; 256]
@@ -0,0 +1,74 @@
+use nbt_serde;
+use std::io::Write;
+
+/// Value of the `version` tag for worlds stored in the McRegion format, as used by Beta 1.3 through release 1.1.
+pub const VERSION_MCREGION: i32 = 19132;
+
+/// Value of the `version` tag for worlds stored in the Anvil format, as used by release 1.2 and later.
+pub const VERSION_ANVIL: i32 = 19133;
+
+/// Root compound of a `level.dat` file.
+#[derive(Debug, Serialize)]
+pub struct LevelRoot {
+	#[serde(rename="Data")]
+	pub data: LevelData
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LevelData {
+	#[serde(rename="LevelName")]        pub name:              String,
+	#[serde(rename="RandomSeed")]       pub seed:              i64,
+	#[serde(rename="generatorName")]    pub generator_name:    String,
+	#[serde(rename="generatorVersion")] pub generator_version: i32,
+	#[serde(rename="version")]          pub version:           i32,
+	#[serde(rename="SpawnX")]           pub spawn_x:           i32,
+	#[serde(rename="SpawnY")]           pub spawn_y:           i32,
+	#[serde(rename="SpawnZ")]           pub spawn_z:           i32,
+	/// Total number of ticks the world has run for.
+	#[serde(rename="Time")]             pub time:              i64,
+	/// Time of day in ticks, only read by release 1.3 and later.
+	#[serde(rename="DayTime")]          pub day_time:          i64,
+	/// Unix time in milliseconds of the last time the world was opened.
+	#[serde(rename="LastPlayed")]       pub last_played:       i64,
+	#[serde(rename="SizeOnDisk")]       pub size_on_disk:      i64,
+	#[serde(rename="GameType")]         pub game_type:         i32,
+	#[serde(rename="MapFeatures")]      pub map_features:      bool,
+	#[serde(rename="raining")]          pub raining:           bool,
+	#[serde(rename="rainTime")]         pub rain_time:         i32,
+	#[serde(rename="thundering")]       pub thundering:        bool,
+	#[serde(rename="thunderTime")]      pub thunder_time:      i32,
+	/// Whether the world has been fully created, otherwise later versions would try to pick a spawn point again.
+	#[serde(rename="initialized")]      pub initialized:       bool
+}
+
+impl LevelData {
+	/// Creates the level data of a freshly generated world, with the spawn point at (0, 64, 0).
+	pub fn new(name: String, seed: u64, version: i32) -> Self {
+		LevelData {
+			name,
+			seed: seed as i64,
+			generator_name: "default".to_string(),
+			generator_version: 1,
+			version,
+			spawn_x: 0,
+			spawn_y: 64,
+			spawn_z: 0,
+			time: 0,
+			day_time: 0,
+			last_played: 0,
+			size_on_disk: 0,
+			game_type: 0,
+			map_features: true,
+			raining: false,
+			rain_time: 0,
+			thundering: false,
+			thunder_time: 0,
+			initialized: true
+		}
+	}
+
+	/// Writes the level data as a gzip compressed NBT file, the format expected for `level.dat`.
+	pub fn write<W>(self, dst: &mut W) -> nbt_serde::Result<()> where W: Write {
+		nbt_serde::encode::to_gzip_writer(dst, &LevelRoot { data: self }, None)
+	}
+}
\ No newline at end of file
@@ -0,0 +1,287 @@
+//! Writer for the McRegion format used by Beta 1.3 through release 1.1, which stores each column as flat arrays
+//! covering the full 16x128x16 volume instead of the sections used by Anvil.
+
+use vocs::indexed::ChunkIndexed;
+use vocs::nibbles::ChunkNibbles;
+use vocs::position::ChunkPosition;
+use entity::Entity;
+use entity::tile::PlacedTileEntity;
+use entity::tick::TileTickNbt;
+use world::extras::ColumnExtras;
+use nbt_serde;
+use serde::{Serialize, Serializer};
+use byteorder::{BigEndian, WriteBytesExt};
+use std::io::{self, Write};
+
+/// Height of a McRegion column in chunks.
+pub const CHUNKS: u8 = 8;
+
+const SECTOR: usize = 4096;
+
+/// Compression type byte for zlib compressed chunks.
+const ZLIB: u8 = 2;
+
+/// Wrapper that makes the NBT serializer write a `TAG_Byte_Array` instead of a list of bytes.
+#[derive(Debug, Clone)]
+pub struct ByteArray(pub Vec<u8>);
+
+impl Serialize for ByteArray {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		serializer.serialize_bytes(&self.0)
+	}
+}
+
+#[derive(Debug, Serialize)]
+struct ColumnRoot {
+	#[serde(rename="Level")]
+	level: Column
+}
+
+/// A single column in the McRegion format.
+#[derive(Debug, Serialize)]
+pub struct Column {
+	#[serde(rename="xPos")]             pub x:            i32,
+	#[serde(rename="zPos")]             pub z:            i32,
+	#[serde(rename="LastUpdate")]       pub last_update:  i64,
+	#[serde(rename="TerrainPopulated")] pub populated:    bool,
+	/// Block IDs, indexed by `x << 11 | z << 7 | y`.
+	#[serde(rename="Blocks")]           pub blocks:       ByteArray,
+	/// Block metadata, as nibbles with the same indexing as the block IDs.
+	#[serde(rename="Data")]             pub data:         ByteArray,
+	#[serde(rename="SkyLight")]         pub sky_light:    ByteArray,
+	#[serde(rename="BlockLight")]       pub block_light:  ByteArray,
+	/// Lowest Y coordinate at which sky light is unobstructed, indexed by `z << 4 | x`.
+	#[serde(rename="HeightMap")]        pub heightmap:    ByteArray,
+	#[serde(rename="Entities")]         pub entities:     Vec<Entity>,
+	#[serde(rename="TileEntities")]     pub tile_entities: Vec<PlacedTileEntity>,
+	/// Scheduled block updates. Beta does not save these and ignores the tag, but later versions will load them.
+	#[serde(rename="TileTicks", skip_serializing_if="Vec::is_empty")] pub tile_ticks: Vec<TileTickNbt>
+}
+
+impl Column {
+	/// Creates an empty column filled with air and no light.
+	pub fn new(x: i32, z: i32) -> Self {
+		Column {
+			x,
+			z,
+			last_update: 0,
+			populated: true,
+			blocks: ByteArray(vec![0; 32768]),
+			data: ByteArray(vec![0; 16384]),
+			sky_light: ByteArray(vec![0; 16384]),
+			block_light: ByteArray(vec![0; 16384]),
+			heightmap: ByteArray(vec![0; 256]),
+			entities: vec![],
+			tile_entities: vec![],
+			tile_ticks: vec![]
+		}
+	}
+
+	/// Stores the tile entities, entities, and scheduled ticks created during population.
+	pub fn set_extras(&mut self, extras: ColumnExtras<u16>) {
+		self.tile_ticks = extras.tile_ticks.iter().map(|tick| tick.to_nbt()).collect();
+		self.tile_entities = extras.tile_entities;
+		self.entities = extras.entities;
+	}
+
+	/// Copies the blocks and light of a chunk into the column. The chunk must be one of the lower 8 chunks.
+	pub fn set_chunk(&mut self, y: u8, blocks: &ChunkIndexed<u16>, sky_light: &ChunkNibbles, block_light: &ChunkNibbles) {
+		assert!(y < CHUNKS, "McRegion columns are only {} chunks tall, tried to set chunk {}", CHUNKS, y);
+
+		let base_y = (y as usize) * 16;
+
+		for x in 0..16 {
+			for z in 0..16 {
+				for chunk_y in 0..16 {
+					let position = ChunkPosition::new(x, chunk_y, z);
+					let index = ((x as usize) << 11) | ((z as usize) << 7) | (base_y + chunk_y as usize);
+
+					let block = *blocks.get(position);
+
+					self.blocks.0[index] = (block >> 4) as u8;
+
+					set_nibble(&mut self.data.0,        index, (block & 15) as u8);
+					set_nibble(&mut self.sky_light.0,   index, sky_light.get(position).raw());
+					set_nibble(&mut self.block_light.0, index, block_light.get(position).raw());
+				}
+			}
+		}
+	}
+
+	/// Copies a heightmap in the ZX order used by Anvil, clamping the heights to the McRegion height limit.
+	pub fn set_heightmap(&mut self, heightmap: &[u32]) {
+		for (index, &height) in heightmap.iter().enumerate() {
+			self.heightmap.0[index] = ::std::cmp::min(height, (CHUNKS as u32) * 16) as u8;
+		}
+	}
+}
+
+fn set_nibble(nibbles: &mut [u8], index: usize, value: u8) {
+	let byte = &mut nibbles[index >> 1];
+
+	if index & 1 == 0 {
+		*byte = (*byte & 0xF0) | (value & 15);
+	} else {
+		*byte = (*byte & 0x0F) | ((value & 15) << 4);
+	}
+}
+
+/// Writes a single `.mcr` region file. The chunks are compressed as they are added, and the file is written at the end.
+pub struct RegionWriter<W> where W: Write {
+	dst: W,
+	/// Compressed data of each chunk, indexed by `z << 5 | x`.
+	chunks: Vec<Option<Vec<u8>>>
+}
+
+impl<W> RegionWriter<W> where W: Write {
+	pub fn start(dst: W) -> Self {
+		RegionWriter {
+			dst,
+			chunks: vec![None; 1024]
+		}
+	}
+
+	/// Adds a column at the given position within the region.
+	pub fn chunk(&mut self, x: u8, z: u8, column: Column) -> nbt_serde::Result<()> {
+		let mut compressed = Vec::new();
+
+		nbt_serde::encode::to_zlib_writer(&mut compressed, &ColumnRoot { level: column }, None)?;
+
+		self.chunks[((z as usize & 31) << 5) | (x as usize & 31)] = Some(compressed);
+
+		Ok(())
+	}
+
+	/// Lays out the chunks in sectors after the location and timestamp tables, and writes the whole file.
+	pub fn finish(mut self) -> io::Result<W> {
+		let mut locations = vec![0u8; SECTOR];
+		let timestamps = vec![0u8; SECTOR];
+		let mut next_sector = 2;
+
+		for (index, chunk) in self.chunks.iter().enumerate() {
+			if let &Some(ref chunk) = chunk {
+				// 4 bytes of length, 1 byte of compression type, and the data, rounded up to whole sectors.
+				let sectors = (chunk.len() + 5 + SECTOR - 1) / SECTOR;
+
+				if sectors > 255 {
+					return Err(io::Error::new(io::ErrorKind::InvalidData, "chunk is too large to be stored in a region file"));
+				}
+
+				locations[index * 4    ] = (next_sector >> 16) as u8;
+				locations[index * 4 + 1] = (next_sector >>  8) as u8;
+				locations[index * 4 + 2] =  next_sector        as u8;
+				locations[index * 4 + 3] =  sectors            as u8;
+
+				next_sector += sectors;
+			}
+		}
+
+		self.dst.write_all(&locations)?;
+		self.dst.write_all(&timestamps)?;
+
+		for chunk in self.chunks.iter().filter_map(|chunk| chunk.as_ref()) {
+			let length = (chunk.len() + 1) as u32;
+			let padding = SECTOR - (chunk.len() + 5) % SECTOR;
+
+			self.dst.write_u32::<BigEndian>(length)?;
+			self.dst.write_u8(ZLIB)?;
+			self.dst.write_all(chunk)?;
+
+			if padding != SECTOR {
+				self.dst.write_all(&vec![0; padding])?;
+			}
+		}
+
+		self.dst.flush()?;
+
+		Ok(self.dst)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use vocs::nibbles::u4;
+
+	fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+		((bytes[offset] as u32) << 24) | ((bytes[offset + 1] as u32) << 16) | ((bytes[offset + 2] as u32) << 8) | (bytes[offset + 3] as u32)
+	}
+
+	#[test]
+	fn test_finish_layout() {
+		let mut writer = RegionWriter::start(Vec::new());
+
+		writer.chunk(3, 0, Column::new(3, 0)).unwrap();
+		writer.chunk(1, 2, Column::new(1, 2)).unwrap();
+
+		let file = writer.finish().unwrap();
+
+		assert_eq!(file.len() % SECTOR, 0);
+
+		let locations = &file[..SECTOR];
+		let timestamps = &file[SECTOR..SECTOR * 2];
+
+		assert!(timestamps.iter().all(|&byte| byte == 0));
+
+		// Chunks are laid out in index order, so (3, 0) comes before (1, 2).
+		let mut expected_sector = 2;
+
+		for index in 0..1024 {
+			let entry = read_u32(locations, index * 4);
+
+			if index != 3 && index != (2 << 5 | 1) {
+				assert_eq!(entry, 0, "unexpected location entry at index {}", index);
+				continue;
+			}
+
+			let offset = (entry >> 8) as usize;
+			let sectors = (entry & 0xFF) as usize;
+
+			assert_eq!(offset, expected_sector);
+			assert!(sectors > 0);
+
+			let start = offset * SECTOR;
+			let length = read_u32(&file, start) as usize;
+
+			assert_eq!(file[start + 4], ZLIB);
+			assert!(length + 4 <= sectors * SECTOR);
+			assert!(length + 4 > (sectors - 1) * SECTOR);
+
+			expected_sector += sectors;
+		}
+
+		assert_eq!(file.len(), expected_sector * SECTOR);
+	}
+
+	#[test]
+	fn test_set_chunk_indexing() {
+		let mut blocks = ChunkIndexed::<u16>::new(4, 0);
+		let mut sky_light = ChunkNibbles::default();
+		let mut block_light = ChunkNibbles::default();
+
+		blocks.ensure_available(35*16 + 14);
+		blocks.ensure_available(17*16 + 1);
+
+		// Adjacent Y values share a byte in the nibble arrays, so cover both halves.
+		blocks.set_immediate(ChunkPosition::new(5, 2, 9), &(35*16 + 14));
+		blocks.set_immediate(ChunkPosition::new(5, 3, 9), &(17*16 + 1));
+		sky_light.set(ChunkPosition::new(5, 2, 9), u4::new(15));
+		block_light.set(ChunkPosition::new(5, 3, 9), u4::new(7));
+
+		let mut column = Column::new(0, 0);
+		column.set_chunk(1, &blocks, &sky_light, &block_light);
+
+		let lower = (5 << 11) | (9 << 7) | 18;
+		let upper = lower + 1;
+
+		assert_eq!(column.blocks.0[lower], 35);
+		assert_eq!(column.blocks.0[upper], 17);
+		assert_eq!(column.data.0[lower >> 1], (1 << 4) | 14);
+		assert_eq!(column.sky_light.0[lower >> 1], 15);
+		assert_eq!(column.block_light.0[lower >> 1], 7 << 4);
+
+		// The rest of the vertical strip stays air.
+		assert!(column.blocks.0[(5 << 11) | (9 << 7)..lower].iter().all(|&block| block == 0));
+		assert!(column.blocks.0[upper + 1..(5 << 11) | (10 << 7)].iter().all(|&block| block == 0));
+	}
+}
\ No newline at end of file
@@ -0,0 +1,4 @@
+//! Writers for the files that make up a world folder: `level.dat`, and the region files of the McRegion format.
+
+pub mod level;
+pub mod mcregion;
\ No newline at end of file
@@ -178,7 +178,7 @@ impl<B> CavesGenerator<B> where B: Target {
 	}
 }
 
-impl<B> StructureGenerator<B> for CavesGenerator<B> where B: Target {
+impl<B> StructureGenerator<B> for CavesGenerator<B> where B: Target + Sync {
 	fn generate(&self, random: Random, column: &mut ColumnMut<B>, chunk: GlobalColumnPosition, from: GlobalColumnPosition, radius: u32) {
 		let mut caves = Caves::for_chunk(random, chunk, from, radius, self.blob_size_multiplier);
 		
@@ -35,7 +35,7 @@ impl<T, B> StructureGenerateNearby<T, B> where T: StructureGenerator<B>, B: Targ
 	}
 }
 
-impl<T, B> Pass<B> for StructureGenerateNearby<T, B> where T: StructureGenerator<B>, B: Target {
+impl<T, B> Pass<B> for StructureGenerateNearby<T, B> where T: StructureGenerator<B>, B: Target + Sync {
 	fn apply(&self, target: &mut ColumnMut<B>, chunk: GlobalColumnPosition) {
 		let radius = self.radius as i32;
 
@@ -53,6 +53,6 @@ impl<T, B> Pass<B> for StructureGenerateNearby<T, B> where T: StructureGenerator
 	}
 }
 
-pub trait StructureGenerator<B> where B: Target {
+pub trait StructureGenerator<B>: Sync where B: Target {
 	fn generate(&self, random: Random, column: &mut ColumnMut<B>, chunk_pos: GlobalColumnPosition, from: GlobalColumnPosition, radius: u32);
 }
\ No newline at end of file
@@ -0,0 +1,138 @@
+use vocs::position::{GlobalColumnPosition, QuadPosition};
+use entity::Entity;
+use entity::tile::{TileEntity, PlacedTileEntity};
+use entity::tick::{TileTick, PlacedTileTick};
+use decorator::quad::QuadExtras;
+use std::collections::HashMap;
+
+/// Everything created during population that is stored in the chunk NBT next to the blocks.
+#[derive(Debug, Clone)]
+pub struct ColumnExtras<B> {
+	pub tile_entities: Vec<PlacedTileEntity>,
+	/// Entities, with absolute positions.
+	pub entities: Vec<Entity>,
+	pub tile_ticks: Vec<PlacedTileTick<B>>
+}
+
+impl<B> Default for ColumnExtras<B> {
+	fn default() -> Self {
+		ColumnExtras {
+			tile_entities: Vec::new(),
+			entities: Vec::new(),
+			tile_ticks: Vec::new()
+		}
+	}
+}
+
+/// Collects the extras of each column as the quads around it are populated, until the column is written out.
+#[derive(Debug)]
+pub struct Extras<B> {
+	columns: HashMap<(i32, i32), ColumnExtras<B>>
+}
+
+impl<B> Default for Extras<B> {
+	fn default() -> Self {
+		Extras {
+			columns: HashMap::new()
+		}
+	}
+}
+
+impl<B> Extras<B> {
+	pub fn new() -> Self {
+		Extras::default()
+	}
+
+	/// Distributes the extras of a populated quad to the columns that contain them. `column` is the column at the
+	/// lower corner of the quad.
+	pub fn add_quad(&mut self, column: GlobalColumnPosition, quad: QuadExtras<B>) {
+		for (position, tile_entity) in quad.tile_entities {
+			self.add_tile_entity(column, position, tile_entity);
+		}
+
+		for entity in quad.entities {
+			self.add_entity(column, entity);
+		}
+
+		for (position, tick) in quad.tile_ticks {
+			self.add_tile_tick(column, position, tick);
+		}
+	}
+
+	/// Adds a tile entity at a position within the quad of the column, assigning it to the column that contains it.
+	pub fn add_tile_entity(&mut self, column: GlobalColumnPosition, position: QuadPosition, tile_entity: TileEntity) {
+		let (x, y, z) = absolute(column, position);
+
+		let placed = PlacedTileEntity { x, y, z, tile_entity };
+		let tile_entities = &mut self.column_mut(x >> 4, z >> 4).tile_entities;
+
+		tile_entities.retain(|existing| (existing.x, existing.y, existing.z) != (placed.x, placed.y, placed.z));
+		tile_entities.push(placed);
+	}
+
+	/// Adds an entity with a position relative to the quad of the column, assigning it to the column that contains it.
+	pub fn add_entity(&mut self, column: GlobalColumnPosition, mut entity: Entity) {
+		entity.position.0 += (column.x() * 16) as f64;
+		entity.position.2 += (column.z() * 16) as f64;
+
+		let (x, z) = (entity.position.0.floor() as i32, entity.position.2.floor() as i32);
+
+		self.column_mut(x >> 4, z >> 4).entities.push(entity);
+	}
+
+	/// Adds a scheduled tick at a position within the quad of the column, assigning it to the column that contains it.
+	pub fn add_tile_tick(&mut self, column: GlobalColumnPosition, position: QuadPosition, tick: TileTick<B>) {
+		let (x, y, z) = absolute(column, position);
+
+		self.column_mut(x >> 4, z >> 4).tile_ticks.push(PlacedTileTick { x, y, z, tick });
+	}
+
+	pub fn get(&self, column: GlobalColumnPosition) -> Option<&ColumnExtras<B>> {
+		self.columns.get(&(column.x(), column.z()))
+	}
+
+	/// Removes the extras of the column, returning them. Columns without any extras return an empty `ColumnExtras`.
+	pub fn take(&mut self, column: GlobalColumnPosition) -> ColumnExtras<B> {
+		self.columns.remove(&(column.x(), column.z())).unwrap_or_else(ColumnExtras::default)
+	}
+
+	fn column_mut(&mut self, x: i32, z: i32) -> &mut ColumnExtras<B> {
+		self.columns.entry((x, z)).or_insert_with(ColumnExtras::default)
+	}
+}
+
+fn absolute(column: GlobalColumnPosition, position: QuadPosition) -> (i32, i32, i32) {
+	(
+		column.x() * 16 + position.x() as i32,
+		position.y() as i32,
+		column.z() * 16 + position.z() as i32
+	)
+}
+
+#[cfg(test)]
+mod test {
+	use super::Extras;
+	use entity::Entity;
+	use entity::tile::TileEntity;
+	use vocs::position::{GlobalColumnPosition, QuadPosition};
+
+	#[test]
+	fn test_extras_assigned_to_containing_column() {
+		let mut extras = Extras::<u16>::new();
+		let quad = GlobalColumnPosition::new(-1, 2);
+
+		extras.add_tile_entity(quad, QuadPosition::new(20, 64, 3), TileEntity::MobSpawner { entity: "Zombie".to_string(), delay: 20 });
+		extras.add_entity(quad, Entity::new("Pig".to_string(), (4.5, 70.0, 17.5)));
+
+		let column = extras.take(GlobalColumnPosition::new(0, 2));
+
+		assert_eq!(column.tile_entities.len(), 1);
+		assert_eq!((column.tile_entities[0].x, column.tile_entities[0].y, column.tile_entities[0].z), (4, 64, 35));
+		assert!(column.entities.is_empty());
+
+		let column = extras.take(GlobalColumnPosition::new(-1, 3));
+
+		assert!(column.tile_entities.is_empty());
+		assert_eq!(column.entities[0].position, (-11.5, 70.0, 49.5));
+	}
+}
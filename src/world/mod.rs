@@ -0,0 +1,254 @@
+use java_rand::Random;
+use vocs::indexed::{ChunkIndexed, Target};
+use vocs::world::world::World;
+use vocs::view::ColumnMut;
+use vocs::position::{GlobalColumnPosition, LayerPosition};
+use decorator::{self, Populator};
+use decorator::quad::Quad;
+use generator::Pass;
+use biome::source::BiomeSource;
+use rayon::prelude::*;
+
+pub mod extras;
+pub mod population;
+pub mod region;
+pub mod spawn;
+pub mod spill;
+
+use self::extras::Extras;
+use self::population::PopulationScheduler;
+use self::spill::Spills;
+
+#[derive(Debug)]
+pub enum Error {
+	/// The column, or one of its +X/+Z/+XZ neighbors, has not had its terrain generated yet.
+	MissingNeighbors(GlobalColumnPosition)
+}
+
+impl From<decorator::Error> for Error {
+	fn from(from: decorator::Error) -> Self {
+		match from {}
+	}
+}
+
+/// Owns a configured generation pipeline: the terrain passes that are run on each column independently,
+/// and the populator that is run on a 2x2 quad of columns once the terrain is in place.
+pub struct WorldGenerator<B> where B: Target {
+	seed: u64,
+	/// Coefficients used to derive the population seed of a column, see `population_rng`.
+	coefficients: (i64, i64),
+	/// Block that new, empty chunks are filled with.
+	empty: B,
+	terrain: Vec<Box<Pass<B>>>,
+	populator: Box<Populator<B>>,
+	biomes: BiomeSource<B>
+}
+
+impl<B> WorldGenerator<B> where B: Target {
+	pub fn new(seed: u64, empty: B, terrain: Vec<Box<Pass<B>>>, populator: Box<Populator<B>>, biomes: BiomeSource<B>) -> Self {
+		let mut rng = Random::new(seed);
+
+		WorldGenerator {
+			seed,
+			coefficients: (
+				((rng.next_i64() >> 1) << 1) + 1,
+				((rng.next_i64() >> 1) << 1) + 1
+			),
+			empty,
+			terrain,
+			populator,
+			biomes
+		}
+	}
+
+	pub fn seed(&self) -> u64 {
+		self.seed
+	}
+
+	/// The biomes of the world, as used by the terrain passes.
+	pub fn biomes(&self) -> &BiomeSource<B> {
+		&self.biomes
+	}
+
+	/// Returns the biome IDs of a column, in the ZX order used by the biome array of a chunk.
+	pub fn biome_ids(&self, position: GlobalColumnPosition) -> Vec<u8> {
+		let layer = self.biomes.layer(position);
+		let mut ids = Vec::with_capacity(256);
+
+		for z in 0..16 {
+			for x in 0..16 {
+				ids.push(layer.get(LayerPosition::new(x, z)).id);
+			}
+		}
+
+		ids
+	}
+
+	/// Creates the RNG used for populating a column, using the same seeding as the Notchian generator.
+	pub fn population_rng(&self, position: GlobalColumnPosition) -> Random {
+		let x_part = (position.x() as i64).wrapping_mul(self.coefficients.0) as u64;
+		let z_part = (position.z() as i64).wrapping_mul(self.coefficients.1) as u64;
+
+		Random::new((x_part.wrapping_add(z_part)) ^ self.seed)
+	}
+
+	/// Runs every terrain pass on a single column, returning the resulting chunks from bottom to top.
+	pub fn generate_column(&self, position: GlobalColumnPosition) -> [ChunkIndexed<B>; 16] {
+		generate_column(&self.terrain, &self.empty, position)
+	}
+
+	/// Decorates the quad formed by this column and its +X, +Z, and +XZ neighbors.
+	/// Like the Notchian generator, decorations are offset by 8 blocks on the X and Z axis, so that they are centered
+	/// on the corner shared by the 4 columns. Tile entities, entities, and scheduled ticks created by the populator
+	/// are added to `extras`, and blocks placed outside of the quad are added to `spills`, along with the amount of
+	/// decoration attempts skipped because they were outside of the quad.
+	pub fn populate_column(&self, world: &mut World<ChunkIndexed<B>>, extras: &mut Extras<B>, spills: &mut Spills<B>, position: GlobalColumnPosition) -> Result<(), Error> {
+		let (spilled, skipped) = {
+			let mut quad = Quad::new(world.get_quad_mut(position).ok_or(Error::MissingNeighbors(position))?);
+			let mut rng = self.population_rng(position);
+
+			self.populator.populate(&mut quad, &mut rng, position)?;
+
+			let spilled = quad.take_spilled();
+			let skipped = quad.skipped();
+			extras.add_quad(position, quad.into_extras());
+
+			(spilled, skipped)
+		};
+
+		spills.add_quad(world, position, spilled);
+		spills.add_skipped(skipped);
+
+		Ok(())
+	}
+}
+
+impl<B> WorldGenerator<B> where B: Target + Send + Sync {
+	/// Generates the terrain of many columns at once, spreading the work across a thread pool.
+	/// Since terrain passes only operate on a single column, the output is identical to calling
+	/// `generate_column` on each position in order.
+	pub fn generate_columns(&self, positions: &[GlobalColumnPosition]) -> Vec<[ChunkIndexed<B>; 16]> {
+		let terrain = &self.terrain;
+		let empty = &self.empty;
+
+		positions.par_iter().map(|&position| generate_column(terrain, empty, position)).collect()
+	}
+
+	/// Generates the terrain for every column in the rectangle from `from` (inclusive) to `to` (exclusive),
+	/// skipping columns that the scheduler already knows about, and populates every column as soon as its
+	/// +X, +Z, and +XZ neighbors exist. Blocks spilled into a column before its terrain existed are placed right
+	/// after generating the terrain.
+	pub fn generate_area(&self, world: &mut World<ChunkIndexed<B>>, extras: &mut Extras<B>, spills: &mut Spills<B>, scheduler: &mut PopulationScheduler, from: GlobalColumnPosition, to: GlobalColumnPosition) -> Result<(), Error> {
+		let mut missing = Vec::new();
+
+		for x in from.x()..to.x() {
+			for z in from.z()..to.z() {
+				let position = GlobalColumnPosition::new(x, z);
+
+				if !scheduler.has_terrain(position) {
+					missing.push(position);
+				}
+			}
+		}
+
+		let generated = self.generate_columns(&missing);
+
+		for (position, column) in missing.into_iter().zip(generated.into_iter()) {
+			world.set_column(position, column);
+			spills.apply(world, position);
+
+			for ready in scheduler.terrain_complete(position) {
+				self.populate_column(world, extras, spills, ready)?;
+				scheduler.populated(ready);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+fn generate_column<B>(terrain: &[Box<Pass<B>>], empty: &B, position: GlobalColumnPosition) -> [ChunkIndexed<B>; 16] where B: Target {
+	let mut column_chunks = [
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone()),
+		ChunkIndexed::<B>::new(4, empty.clone())
+	];
+
+	{
+		let mut column: ColumnMut<B> = ColumnMut::from_array(&mut column_chunks);
+
+		for pass in terrain {
+			pass.apply(&mut column, position);
+		}
+	}
+
+	column_chunks
+}
+
+#[cfg(test)]
+mod test {
+	use super::WorldGenerator;
+	use generator::Pass;
+	use generator::overworld_173::{self, Settings};
+	use biome::{Biome, Surface, Grid, Lookup};
+	use biome::source::BiomeSource;
+	use biome::climate::ClimateSource;
+	use decorator::Populator;
+	use vocs::position::{GlobalColumnPosition, ChunkPosition};
+	use std::borrow::Cow;
+
+	#[test]
+	fn test_generate_columns_matches_sequential() {
+		let seed = 8399452073110208023;
+		let settings = Settings::default();
+		let climate = settings.climate;
+
+		let grid = Grid::new(Biome {
+			surface: Surface { top: 2*16, fill: 3*16, chain: vec![] },
+			name: Cow::Borrowed("Plains"),
+			id: 1
+		});
+
+		let biomes = BiomeSource::new(ClimateSource::new(seed, climate), Lookup::generate(&grid));
+		let (shape, paint) = overworld_173::passes(seed, settings, biomes.clone());
+		let terrain: Vec<Box<Pass<u16>>> = vec![Box::new(shape), Box::new(paint)];
+		let populator: Vec<Box<Populator<u16>>> = Vec::new();
+
+		let generator = WorldGenerator::new(seed, 0*16, terrain, Box::new(populator), biomes);
+
+		let mut positions = Vec::new();
+
+		for x in -2..2 {
+			for z in -2..2 {
+				positions.push(GlobalColumnPosition::new(x, z));
+			}
+		}
+
+		let parallel = generator.generate_columns(&positions);
+
+		for (&position, parallel) in positions.iter().zip(parallel.iter()) {
+			let sequential = generator.generate_column(position);
+
+			for (chunk_y, (parallel, sequential)) in parallel.iter().zip(sequential.iter()).enumerate() {
+				for index in 0..4096 {
+					let at = ChunkPosition::new((index & 15) as u8, (index >> 8) as u8, ((index >> 4) & 15) as u8);
+
+					assert_eq!(parallel.get(at), sequential.get(at), "column {:?}, chunk {}, {:?}", position, chunk_y, at);
+				}
+			}
+		}
+	}
+}
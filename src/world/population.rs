@@ -0,0 +1,104 @@
+use vocs::position::GlobalColumnPosition;
+use std::collections::HashMap;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ColumnState {
+	/// The terrain passes have been run on the column, but it has not been populated yet.
+	Terrain,
+	/// The column has been populated. Note that neighboring columns may still write into it when they are populated.
+	Populated
+}
+
+/// Decides when columns can be populated, following the same rules as the Notchian chunk provider.
+///
+/// Population of a column writes to the quad formed by the column and its +X, +Z, and +XZ neighbors.
+/// Therefore, a column is populated exactly once, as soon as it and those 3 neighbors have their terrain.
+/// When the terrain of a column is completed, the column itself and its -X, -Z, and -XZ neighbors are checked,
+/// in that order, just like in Beta.
+#[derive(Debug, Default)]
+pub struct PopulationScheduler {
+	states: HashMap<(i32, i32), ColumnState>
+}
+
+impl PopulationScheduler {
+	pub fn new() -> Self {
+		PopulationScheduler::default()
+	}
+
+	pub fn state(&self, position: GlobalColumnPosition) -> Option<ColumnState> {
+		self.states.get(&(position.x(), position.z())).map(|state| *state)
+	}
+
+	pub fn has_terrain(&self, position: GlobalColumnPosition) -> bool {
+		self.state(position).is_some()
+	}
+
+	pub fn is_populated(&self, position: GlobalColumnPosition) -> bool {
+		self.state(position) == Some(ColumnState::Populated)
+	}
+
+	/// Records that the terrain of the column is complete, and returns the columns that are now ready for population.
+	/// The caller should populate them in the returned order, and call `populated` for each one.
+	pub fn terrain_complete(&mut self, position: GlobalColumnPosition) -> Vec<GlobalColumnPosition> {
+		self.states.entry((position.x(), position.z())).or_insert(ColumnState::Terrain);
+
+		let mut ready = Vec::with_capacity(4);
+
+		for &(x_offset, z_offset) in &[(0, 0), (-1, 0), (0, -1), (-1, -1)] {
+			let candidate = GlobalColumnPosition::new(position.x() + x_offset, position.z() + z_offset);
+
+			if self.is_ready(candidate) {
+				ready.push(candidate);
+			}
+		}
+
+		ready
+	}
+
+	/// Records that the column has been populated.
+	pub fn populated(&mut self, position: GlobalColumnPosition) {
+		self.states.insert((position.x(), position.z()), ColumnState::Populated);
+	}
+
+	/// Stops tracking a column, usually because it has been written out and unloaded.
+	pub fn forget(&mut self, position: GlobalColumnPosition) {
+		self.states.remove(&(position.x(), position.z()));
+	}
+
+	/// Whether the column has terrain but has not been populated, and its +X, +Z, and +XZ neighbors all have terrain.
+	fn is_ready(&self, position: GlobalColumnPosition) -> bool {
+		if self.state(position) != Some(ColumnState::Terrain) {
+			return false;
+		}
+
+		self.has_terrain(GlobalColumnPosition::new(position.x() + 1, position.z()    )) &&
+		self.has_terrain(GlobalColumnPosition::new(position.x(),     position.z() + 1)) &&
+		self.has_terrain(GlobalColumnPosition::new(position.x() + 1, position.z() + 1))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::PopulationScheduler;
+	use vocs::position::GlobalColumnPosition;
+
+	#[test]
+	fn test_populate_when_neighbors_exist() {
+		let mut scheduler = PopulationScheduler::new();
+
+		assert!(scheduler.terrain_complete(GlobalColumnPosition::new(0, 0)).is_empty());
+		assert!(scheduler.terrain_complete(GlobalColumnPosition::new(1, 0)).is_empty());
+		assert!(scheduler.terrain_complete(GlobalColumnPosition::new(0, 1)).is_empty());
+
+		assert_eq!(scheduler.terrain_complete(GlobalColumnPosition::new(1, 1)), vec![GlobalColumnPosition::new(0, 0)]);
+		scheduler.populated(GlobalColumnPosition::new(0, 0));
+
+		assert_eq!(scheduler.terrain_complete(GlobalColumnPosition::new(2, 0)), vec![]);
+		assert_eq!(scheduler.terrain_complete(GlobalColumnPosition::new(2, 1)), vec![GlobalColumnPosition::new(1, 0)]);
+		scheduler.populated(GlobalColumnPosition::new(1, 0));
+
+		assert!(scheduler.is_populated(GlobalColumnPosition::new(0, 0)));
+		assert!(scheduler.is_populated(GlobalColumnPosition::new(1, 0)));
+		assert!(!scheduler.is_populated(GlobalColumnPosition::new(1, 1)));
+	}
+}
\ No newline at end of file
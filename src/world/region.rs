@@ -0,0 +1,277 @@
+use vocs::indexed::{ChunkIndexed, Target};
+use vocs::world::world::World;
+use vocs::position::{GlobalColumnPosition, GlobalChunkPosition};
+use world::{WorldGenerator, Error};
+use world::extras::{Extras, ColumnExtras};
+use world::population::PopulationScheduler;
+use world::spill::Spills;
+use std::collections::HashSet;
+use std::cmp::{min, max};
+
+/// Position of a region, a 32x32 area of columns that is stored in a single region file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RegionPosition {
+	x: i32,
+	z: i32
+}
+
+impl RegionPosition {
+	pub fn new(x: i32, z: i32) -> Self {
+		RegionPosition { x, z }
+	}
+
+	pub fn containing(column: GlobalColumnPosition) -> Self {
+		RegionPosition {
+			x: column.x() >> 5,
+			z: column.z() >> 5
+		}
+	}
+
+	pub fn x(&self) -> i32 {
+		self.x
+	}
+
+	pub fn z(&self) -> i32 {
+		self.z
+	}
+
+	/// Minimum column of the region, inclusive.
+	pub fn lower(&self) -> GlobalColumnPosition {
+		GlobalColumnPosition::new(self.x * 32, self.z * 32)
+	}
+
+	/// Maximum column of the region, exclusive.
+	pub fn upper(&self) -> GlobalColumnPosition {
+		GlobalColumnPosition::new(self.x * 32 + 32, self.z * 32 + 32)
+	}
+}
+
+/// Width of the border of columns that is generated around a region. Lighting the region needs its direct neighbors
+/// to be complete, and those neighbors are only complete once their own +X/+Z neighbors have been generated.
+pub const BORDER: i32 = 2;
+
+/// Generates a rectangular area of columns one region at a time, so that arbitrarily large areas can be generated
+/// without keeping every column in memory.
+///
+/// Preparing a region generates the terrain of the region and a `BORDER` column wide border around it. Columns are
+/// populated by the `PopulationScheduler` as soon as their neighbors exist, so by the time the preparation is done,
+/// the columns of the region and the columns directly adjacent to it are complete. The region can then be lit and
+/// written out. Releasing the region then drops every column that no remaining region will need.
+pub struct RegionStream<B> where B: Target + Send + Sync {
+	world: World<ChunkIndexed<B>>,
+	/// Tile entities, entities, and scheduled ticks created while populating the loaded columns.
+	extras: Extras<B>,
+	/// Blocks placed by decorators into columns that have not been generated yet.
+	spills: Spills<B>,
+	/// Minimum column of the area, inclusive.
+	from: GlobalColumnPosition,
+	/// Maximum column of the area, exclusive.
+	to: GlobalColumnPosition,
+	/// Tracks the columns that are currently loaded, and which of them have been populated.
+	scheduler: PopulationScheduler,
+	/// Regions that have not been released yet.
+	remaining: HashSet<RegionPosition>
+}
+
+impl<B> RegionStream<B> where B: Target + Send + Sync {
+	pub fn new(from: GlobalColumnPosition, to: GlobalColumnPosition) -> Self {
+		let mut stream = RegionStream {
+			world: World::new(),
+			extras: Extras::new(),
+			spills: Spills::new(),
+			from,
+			to,
+			scheduler: PopulationScheduler::new(),
+			remaining: HashSet::new()
+		};
+
+		for region in stream.regions() {
+			stream.remaining.insert(region);
+		}
+
+		stream
+	}
+
+	/// Returns every region that overlaps the area, ordered by Z and then X.
+	pub fn regions(&self) -> Vec<RegionPosition> {
+		let lower = RegionPosition::containing(self.from);
+		let upper = RegionPosition::containing(GlobalColumnPosition::new(self.to.x() - 1, self.to.z() - 1));
+
+		let mut regions = Vec::new();
+
+		for z in lower.z()..(upper.z() + 1) {
+			for x in lower.x()..(upper.x() + 1) {
+				regions.push(RegionPosition::new(x, z));
+			}
+		}
+
+		regions
+	}
+
+	/// Returns the part of the region that lies within the area as a (lower inclusive, upper exclusive) pair.
+	pub fn bounds(&self, region: RegionPosition) -> (GlobalColumnPosition, GlobalColumnPosition) {
+		let (lower, upper) = (region.lower(), region.upper());
+
+		(
+			GlobalColumnPosition::new(max(lower.x(), self.from.x()), max(lower.z(), self.from.z())),
+			GlobalColumnPosition::new(min(upper.x(), self.to.x()),   min(upper.z(), self.to.z()))
+		)
+	}
+
+	pub fn world(&self) -> &World<ChunkIndexed<B>> {
+		&self.world
+	}
+
+	pub fn world_mut(&mut self) -> &mut World<ChunkIndexed<B>> {
+		&mut self.world
+	}
+
+	/// Removes the extras of a column so that they can be written out. Should only be called once the region
+	/// containing the column has been prepared, as populating the neighbors of a column can still add to its extras.
+	pub fn take_extras(&mut self, column: GlobalColumnPosition) -> ColumnExtras<B> {
+		self.extras.take(column)
+	}
+
+	/// Generates and populates everything needed to complete and light the columns of the region that lie within the area.
+	pub fn prepare(&mut self, generator: &WorldGenerator<B>, region: RegionPosition) -> Result<(), Error> {
+		let (lower, upper) = self.bounds(region);
+
+		generator.generate_area(
+			&mut self.world,
+			&mut self.extras,
+			&mut self.spills,
+			&mut self.scheduler,
+			GlobalColumnPosition::new(lower.x() - BORDER, lower.z() - BORDER),
+			GlobalColumnPosition::new(upper.x() + BORDER, upper.z() + BORDER)
+		)
+	}
+
+	/// Marks the region as written, and drops every column around it that is no longer needed by any other region.
+	/// Blocks spilled into the region from now on, or into columns that no remaining region will generate, are dropped.
+	pub fn release(&mut self, region: RegionPosition) {
+		self.remaining.remove(&region);
+		self.spills.mark_written(region);
+
+		let (lower, upper) = self.bounds(region);
+
+		for x in (lower.x() - BORDER)..(upper.x() + BORDER) {
+			for z in (lower.z() - BORDER)..(upper.z() + BORDER) {
+				let position = GlobalColumnPosition::new(x, z);
+
+				if !self.is_loaded(position) || self.is_needed(position) {
+					continue;
+				}
+
+				for y in 0..16 {
+					self.world.remove(GlobalChunkPosition::from_column(position, y));
+				}
+
+				self.extras.take(position);
+				self.spills.discard(position);
+
+				self.scheduler.forget(position);
+			}
+		}
+
+		for target in self.spills.targets() {
+			if !self.is_loaded(target) && !self.is_needed(target) {
+				self.spills.discard(target);
+			}
+		}
+	}
+
+	/// Returns the amount of blocks that decorators placed into columns that were already written out, or that
+	/// are outside of the generated area, and that were therefore dropped.
+	pub fn dropped_spills(&self) -> usize {
+		self.spills.dropped()
+	}
+
+	/// Returns the amount of decoration attempts that were skipped because they were outside of their quad, such as
+	/// clumps with a large spread.
+	pub fn skipped_decorations(&self) -> usize {
+		self.spills.skipped()
+	}
+
+	fn is_loaded(&self, position: GlobalColumnPosition) -> bool {
+		self.scheduler.has_terrain(position)
+	}
+
+	/// Whether a remaining region still needs the column, either because the column is within that region
+	/// or because the column is part of the border around that region.
+	fn is_needed(&self, position: GlobalColumnPosition) -> bool {
+		for x_offset in -BORDER..(BORDER + 1) {
+			for z_offset in -BORDER..(BORDER + 1) {
+				let neighbor = GlobalColumnPosition::new(position.x() + x_offset, position.z() + z_offset);
+				let region = RegionPosition::containing(neighbor);
+
+				if !self.remaining.contains(&region) {
+					continue;
+				}
+
+				let (lower, upper) = self.bounds(region);
+
+				if position.x() >= lower.x() - BORDER && position.x() < upper.x() + BORDER && position.z() >= lower.z() - BORDER && position.z() < upper.z() + BORDER {
+					return true;
+				}
+			}
+		}
+
+		false
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{RegionStream, RegionPosition};
+	use world::WorldGenerator;
+	use decorator::{Populator, Result};
+	use decorator::quad::Quad;
+	use generator::Pass;
+	use matcher::BlockMatcher;
+	use java_rand::Random;
+	use vocs::position::{GlobalColumnPosition, GlobalChunkPosition, ChunkPosition};
+
+	/// Spills a single block 2 columns in the -X direction of the column being populated.
+	struct SpillBehind;
+
+	impl Populator<u16> for SpillBehind {
+		fn populate(&self, quad: &mut Quad<u16>, _: &mut Random, _: GlobalColumnPosition) -> Result {
+			quad.set_or_spill((-17, 64, 8), &(1*16), &BlockMatcher::all());
+
+			Ok(())
+		}
+	}
+
+	fn spilled_block(stream: &RegionStream<u16>, column: GlobalColumnPosition) -> u16 {
+		*stream.world().get(GlobalChunkPosition::from_column(column, 4)).unwrap().get(ChunkPosition::new(15, 0, 8))
+	}
+
+	#[test]
+	fn test_spills_across_region_boundary() {
+		let terrain: Vec<Box<Pass<u16>>> = Vec::new();
+		let generator = WorldGenerator::new(0, 0, terrain, Box::new(SpillBehind), ::test::plains());
+
+		// 2 columns on each side of the boundary between regions 0 and 1.
+		let mut stream = RegionStream::new(GlobalColumnPosition::new(30, 0), GlobalColumnPosition::new(34, 1));
+		assert_eq!(stream.regions(), vec![RegionPosition::new(0, 0), RegionPosition::new(1, 0)]);
+
+		// Columns 28 to 32 are populated, for Z from -2 to 1. Columns 28 and 29 spill into 26 and 27, which are never
+		// generated, so those 8 blocks are dropped when the region is released.
+		stream.prepare(&generator, RegionPosition::new(0, 0)).unwrap();
+		assert_eq!(spilled_block(&stream, GlobalColumnPosition::new(30, 0)), 1*16);
+		assert_eq!(spilled_block(&stream, GlobalColumnPosition::new(31, 0)), 0);
+
+		stream.release(RegionPosition::new(0, 0));
+		assert_eq!(stream.dropped_spills(), 8);
+
+		// Column 31 is still loaded as the border of region 1, but has been written out, so the blocks spilled into it
+		// by columns 33 are dropped instead of being lost silently. Column 32 is in region 1, and receives its block.
+		stream.prepare(&generator, RegionPosition::new(1, 0)).unwrap();
+		assert_eq!(spilled_block(&stream, GlobalColumnPosition::new(31, 0)), 0);
+		assert_eq!(spilled_block(&stream, GlobalColumnPosition::new(32, 0)), 1*16);
+		assert_eq!(stream.dropped_spills(), 12);
+
+		stream.release(RegionPosition::new(1, 0));
+		assert_eq!(stream.dropped_spills(), 12);
+	}
+}
\ No newline at end of file
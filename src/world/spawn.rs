@@ -0,0 +1,68 @@
+use java_rand::Random;
+use vocs::indexed::{ChunkIndexed, Target};
+use vocs::position::{ChunkPosition, GlobalColumnPosition};
+use matcher::BlockMatcher;
+use world::WorldGenerator;
+use std::collections::HashMap;
+
+/// The Notchian spawn search has no limit, but it would never end in a world without any matching blocks.
+const MAX_ATTEMPTS: u32 = 65536;
+
+/// Y coordinate of the spawn point, the actual spawn height is decided by the game when the player joins.
+pub const SPAWN_Y: i32 = 64;
+
+/// Height of a Beta world. Blocks at or above it count as empty, so the walk upwards stops below it.
+const HEIGHT_LIMIT: u8 = 128;
+
+impl<B> WorldGenerator<B> where B: Target {
+	/// Finds the spawn point like Beta does: starting at the origin, randomly walk around until the first uncovered
+	/// block at or above Y=63 matches `spawn_on`, which is sand in vanilla. Only the terrain passes are used, so the
+	/// search does not depend on what has been generated already.
+	///
+	/// Beta uses an unseeded RNG for this, so the spawn point differs each time a world is created.
+	/// Passing an RNG seeded with the world seed makes the result reproducible instead.
+	pub fn find_spawn(&self, spawn_on: &BlockMatcher<B>, rng: &mut Random) -> Option<(i32, i32, i32)> {
+		let mut columns = HashMap::<(i32, i32), Vec<B>>::new();
+		let (mut x, mut z) = (0, 0);
+
+		for _ in 0..MAX_ATTEMPTS {
+			let column = columns.entry((x >> 4, z >> 4)).or_insert_with(|| {
+				self.uncovered_blocks(GlobalColumnPosition::new(x >> 4, z >> 4))
+			});
+
+			if spawn_on.matches(&column[(((z & 15) << 4) | (x & 15)) as usize]) {
+				return Some((x, SPAWN_Y, z));
+			}
+
+			x += rng.next_u32_bound(64) as i32 - rng.next_u32_bound(64) as i32;
+			z += rng.next_u32_bound(64) as i32 - rng.next_u32_bound(64) as i32;
+		}
+
+		None
+	}
+
+	/// Returns the first uncovered block of each position in the column, in ZX order. Like the Notchian
+	/// implementation, this starts at Y=63 and moves up until the block above is empty.
+	fn uncovered_blocks(&self, position: GlobalColumnPosition) -> Vec<B> {
+		let chunks = self.generate_column(position);
+		let mut uncovered = Vec::with_capacity(256);
+
+		for z in 0..16 {
+			for x in 0..16 {
+				let mut y = 63;
+
+				while y + 1 < HEIGHT_LIMIT && get(&chunks, x, y + 1, z) != &self.empty {
+					y += 1;
+				}
+
+				uncovered.push(get(&chunks, x, y, z).clone());
+			}
+		}
+
+		uncovered
+	}
+}
+
+fn get<B>(chunks: &[ChunkIndexed<B>; 16], x: u8, y: u8, z: u8) -> &B where B: Target {
+	chunks[(y >> 4) as usize].get(ChunkPosition::new(x, y & 15, z))
+}
\ No newline at end of file
@@ -0,0 +1,192 @@
+use vocs::indexed::{ChunkIndexed, Target};
+use vocs::world::world::World;
+use vocs::position::{GlobalColumnPosition, GlobalChunkPosition, ChunkPosition};
+use decorator::quad::SpilledBlock;
+use matcher::BlockMatcher;
+use world::region::RegionPosition;
+use std::collections::{HashMap, HashSet};
+
+/// A spilled block, with a position relative to the column that contains it.
+#[derive(Debug, Clone)]
+struct Pending<B> where B: Target {
+	position: (u8, u8, u8),
+	block: B,
+	replace: BlockMatcher<B>
+}
+
+/// Collects the blocks that decorators place outside of the quad that they are decorating. Like the Notchian
+/// generator, which generates the terrain of a chunk as soon as a decorator writes into it, the blocks are placed
+/// as soon as the terrain of the column containing them exists, before that column is populated itself.
+///
+/// Blocks spilled into a region that has already been written out, or into a column that will never be generated,
+/// cannot be placed. They are dropped and counted instead, see `dropped`. Decoration attempts that could not run at all
+/// because they were outside of the quad are counted as well, see `skipped`.
+#[derive(Debug)]
+pub struct Spills<B> where B: Target {
+	columns: HashMap<(i32, i32), Vec<Pending<B>>>,
+	/// Regions that have been written out. Their columns may still be loaded as the border of another region.
+	written: HashSet<RegionPosition>,
+	dropped: usize,
+	skipped: usize
+}
+
+impl<B> Default for Spills<B> where B: Target {
+	fn default() -> Self {
+		Spills {
+			columns: HashMap::new(),
+			written: HashSet::new(),
+			dropped: 0,
+			skipped: 0
+		}
+	}
+}
+
+impl<B> Spills<B> where B: Target {
+	pub fn new() -> Self {
+		Spills::default()
+	}
+
+	/// Adds the blocks spilled by a populated quad, placing them right away in columns that already exist.
+	/// `column` is the column at the lower corner of the quad. Blocks spilled into written regions are dropped.
+	pub fn add_quad(&mut self, world: &mut World<ChunkIndexed<B>>, column: GlobalColumnPosition, spilled: Vec<SpilledBlock<B>>) {
+		let mut targets = Vec::new();
+
+		for spilled in spilled {
+			let x = column.x() * 16 + spilled.position.0;
+			let z = column.z() * 16 + spilled.position.2;
+
+			let target = (x >> 4, z >> 4);
+
+			if self.written.contains(&RegionPosition::containing(GlobalColumnPosition::new(target.0, target.1))) {
+				self.dropped += 1;
+				continue;
+			}
+
+			self.columns.entry(target).or_insert_with(Vec::new).push(Pending {
+				position: ((x & 15) as u8, spilled.position.1, (z & 15) as u8),
+				block: spilled.block,
+				replace: spilled.replace
+			});
+
+			if !targets.contains(&target) {
+				targets.push(target);
+			}
+		}
+
+		for (x, z) in targets {
+			let target = GlobalColumnPosition::new(x, z);
+
+			if world.get(GlobalChunkPosition::from_column(target, 0)).is_some() {
+				self.apply(world, target);
+			}
+		}
+	}
+
+	/// Places every block spilled into the column so far. The terrain of the column must already exist.
+	pub fn apply(&mut self, world: &mut World<ChunkIndexed<B>>, column: GlobalColumnPosition) {
+		let pending = match self.columns.remove(&(column.x(), column.z())) {
+			Some(pending) => pending,
+			None => return
+		};
+
+		for Pending { position: (x, y, z), block, replace } in pending {
+			let chunk = match world.get_mut(GlobalChunkPosition::from_column(column, y >> 4)) {
+				Some(chunk) => chunk,
+				None => continue
+			};
+
+			let position = ChunkPosition::new(x, y & 15, z);
+
+			if replace.matches(chunk.get(position)) {
+				chunk.set_immediate(position, &block);
+			}
+		}
+	}
+
+	/// Counts decoration attempts that a populated quad skipped because they were outside of the quad.
+	pub fn add_skipped(&mut self, skipped: usize) {
+		self.skipped += skipped;
+	}
+
+	/// Drops the blocks spilled into a column, used when the column will never be generated.
+	pub fn discard(&mut self, column: GlobalColumnPosition) {
+		if let Some(pending) = self.columns.remove(&(column.x(), column.z())) {
+			self.dropped += pending.len();
+		}
+	}
+
+	/// Marks every column of the region as written out. Blocks spilled into the region from now on are dropped,
+	/// even if its columns are still loaded.
+	pub fn mark_written(&mut self, region: RegionPosition) {
+		self.written.insert(region);
+	}
+
+	/// Returns the columns that have blocks waiting for their terrain to exist.
+	pub fn targets(&self) -> Vec<GlobalColumnPosition> {
+		self.columns.keys().map(|&(x, z)| GlobalColumnPosition::new(x, z)).collect()
+	}
+
+	/// Returns the amount of spilled blocks that are still waiting for their column to exist.
+	pub fn len(&self) -> usize {
+		self.columns.values().map(Vec::len).sum()
+	}
+
+	/// Returns the amount of spilled blocks that were dropped because their region was already written out,
+	/// or because their column will never be generated.
+	pub fn dropped(&self) -> usize {
+		self.dropped
+	}
+
+	/// Returns the amount of decoration attempts that were skipped because they were outside of their quad.
+	pub fn skipped(&self) -> usize {
+		self.skipped
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.columns.is_empty()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Spills;
+	use decorator::quad::SpilledBlock;
+	use matcher::BlockMatcher;
+	use vocs::indexed::ChunkIndexed;
+	use vocs::world::world::World;
+	use vocs::position::{GlobalColumnPosition, GlobalChunkPosition, ChunkPosition};
+
+	fn column() -> [ChunkIndexed<u16>; 16] {
+		[
+			ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0),
+			ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0),
+			ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0),
+			ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0), ChunkIndexed::new(4, 0)
+		]
+	}
+
+	#[test]
+	fn test_spilled_blocks_placed_once_column_exists() {
+		let mut world = World::<ChunkIndexed<u16>>::new();
+		let mut spills = Spills::new();
+
+		let spilled = vec![
+			SpilledBlock { position: (-1, 70, 5), block: 18*16, replace: BlockMatcher::all() },
+			SpilledBlock { position: (-2, 70, 5), block: 18*16, replace: BlockMatcher::is(1*16) }
+		];
+
+		spills.add_quad(&mut world, GlobalColumnPosition::new(3, 0), spilled);
+		assert_eq!(spills.len(), 2);
+
+		let target = GlobalColumnPosition::new(2, 0);
+
+		world.set_column(target, column());
+		spills.apply(&mut world, target);
+		assert!(spills.is_empty());
+
+		let chunk = world.get(GlobalChunkPosition::from_column(target, 4)).unwrap();
+
+		assert_eq!(*chunk.get(ChunkPosition::new(15, 6, 5)), 18*16);
+		assert_eq!(*chunk.get(ChunkPosition::new(14, 6, 5)), 0);
+	}
+}